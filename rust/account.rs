@@ -0,0 +1,668 @@
+// Account data decoders: turn the base64 data and owner returned by getAccountInfo into typed
+// structs for SPL Token and Token-2022 mints and token accounts, and for the token marketplace
+// program's accounts. Raw amounts are kept as strings next to their decimal form, since they
+// can exceed what a JavaScript number holds exactly.
+use crate::marketplace::{Marketplace, SellOrder, SellOrderV1, SellOrderV2, SellerState};
+use crate::token::{format_token_amount, TokenProgram};
+use crate::{parse_pubkey, KeypairError};
+use base64::decode;
+use borsh::BorshDeserialize;
+use chrono::{SecondsFormat, TimeZone, Utc};
+use serde::Serialize;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::program_option::COption;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::cpi_guard::CpiGuard;
+use spl_token_2022::extension::default_account_state::DefaultAccountState;
+use spl_token_2022::extension::interest_bearing_mint::InterestBearingConfig;
+use spl_token_2022::extension::memo_transfer::MemoTransfer;
+use spl_token_2022::extension::mint_close_authority::MintCloseAuthority;
+use spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+use spl_token_2022::extension::transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig};
+use spl_token_2022::extension::{BaseState, BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::state::{Account, AccountState, Mint};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountDecodeOptions {
+    // Where the marketplace program is deployed; its accounts are rejected without it
+    pub marketplace_program_id: Option<Pubkey>,
+    // Decimals of the mint, used to format token account and sell order amounts
+    pub decimals: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DecodedAccount {
+    Mint(MintAccount),
+    TokenAccount(TokenAccount),
+    Marketplace(MarketplaceAccount),
+    SellOrder(SellOrderAccount),
+    SellerState(SellerStateAccount),
+}
+
+// A Token-2022 extension with its fields rendered as text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenExtension {
+    pub name: String,
+    pub fields: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MintAccount {
+    pub token_program: String,
+    pub mint_authority: Option<String>,
+    pub supply: String,
+    pub ui_supply: String,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<String>,
+    pub extensions: Vec<TokenExtension>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenAccount {
+    pub token_program: String,
+    pub mint: String,
+    pub owner: String,
+    pub amount: String,
+    // Only known when the mint's decimals are supplied
+    pub ui_amount: Option<String>,
+    pub delegate: Option<String>,
+    pub delegated_amount: String,
+    // "initialized" or "frozen"
+    pub state: String,
+    // Rent-exempt reserve in lamports, for wrapped SOL accounts
+    pub native_reserve: Option<String>,
+    pub close_authority: Option<String>,
+    pub extensions: Vec<TokenExtension>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketplaceAccount {
+    pub admin: String,
+    pub fee_basis_points: u16,
+    // e.g. "2.5%"
+    pub fee_percent: String,
+    pub total_volume: String,
+    pub total_volume_sol: String,
+    pub total_fees_collected: String,
+    pub total_fees_collected_sol: String,
+    pub is_initialized: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SellOrderAccount {
+    // 1 and 2 are the layouts earlier versions of the program left open, 3 is the current one
+    pub version: u8,
+    // Not recorded by version 1 orders
+    pub marketplace: Option<String>,
+    pub seller: String,
+    pub token_mint: String,
+    // Only recorded by version 3 orders
+    pub nonce: Option<String>,
+    pub amount: String,
+    // Only known when the mint's decimals are supplied
+    pub ui_amount: Option<String>,
+    pub price_per_token: String,
+    pub price_per_token_sol: String,
+    pub created_at: i64,
+    // RFC 3339 form of `created_at`
+    pub created_at_time: Option<String>,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SellerStateAccount {
+    pub marketplace: String,
+    pub seller: String,
+    pub order_count: String,
+}
+
+// Decode an account from its base64 data and the program that owns it
+pub fn decode_account(
+    data_base64: &str,
+    owner: &str,
+    options: &AccountDecodeOptions,
+) -> Result<DecodedAccount, KeypairError> {
+    let owner = parse_pubkey(owner)?;
+    let data = decode(data_base64.trim())?;
+
+    if let Ok(program) = TokenProgram::from_mint_owner(&owner) {
+        return decode_token_account_data(&data, program, options.decimals);
+    }
+    if Some(owner) == options.marketplace_program_id {
+        return decode_marketplace_account_data(&data, options.decimals);
+    }
+    Err(KeypairError::InvalidAccountData(format!(
+        "no decoder for accounts owned by {}",
+        owner
+    )))
+}
+
+fn invalid(reason: impl ToString) -> KeypairError {
+    KeypairError::InvalidAccountData(reason.to_string())
+}
+
+fn token_program_name(program: TokenProgram) -> String {
+    match program {
+        TokenProgram::Token => "spl-token".to_string(),
+        TokenProgram::Token2022 => "spl-token-2022".to_string(),
+    }
+}
+
+fn optional_key(key: COption<Pubkey>) -> Option<String> {
+    Option::<Pubkey>::from(key).map(|key| key.to_string())
+}
+
+// Mints and token accounts are told apart by length: a token account is 165 bytes, and
+// Token-2022 mints with extensions are padded past that and marked with an account type byte
+pub fn decode_token_account_data(
+    data: &[u8],
+    program: TokenProgram,
+    decimals: Option<u8>,
+) -> Result<DecodedAccount, KeypairError> {
+    let token_program = token_program_name(program);
+    let is_account = match program {
+        TokenProgram::Token => data.len() == Account::LEN,
+        TokenProgram::Token2022 => {
+            data.len() == Account::LEN
+                || (data.len() > Account::LEN && data[Account::LEN] == Account::ACCOUNT_TYPE as u8)
+        }
+    };
+
+    if is_account {
+        let state = StateWithExtensions::<Account>::unpack(data).map_err(invalid)?;
+        let account = state.base;
+        let extensions = extensions(&state, decimals)?;
+        let state_name = match account.state {
+            AccountState::Initialized => "initialized",
+            AccountState::Frozen => "frozen",
+            AccountState::Uninitialized => "uninitialized",
+        };
+        return Ok(DecodedAccount::TokenAccount(TokenAccount {
+            token_program,
+            mint: account.mint.to_string(),
+            owner: account.owner.to_string(),
+            amount: account.amount.to_string(),
+            ui_amount: decimals.map(|decimals| format_token_amount(account.amount, decimals)),
+            delegate: optional_key(account.delegate),
+            delegated_amount: account.delegated_amount.to_string(),
+            state: state_name.to_string(),
+            native_reserve: Option::<u64>::from(account.is_native).map(|reserve| reserve.to_string()),
+            close_authority: optional_key(account.close_authority),
+            extensions,
+        }));
+    }
+
+    let state = StateWithExtensions::<Mint>::unpack(data).map_err(invalid)?;
+    let mint = state.base;
+    Ok(DecodedAccount::Mint(MintAccount {
+        token_program,
+        mint_authority: optional_key(mint.mint_authority),
+        supply: mint.supply.to_string(),
+        ui_supply: format_token_amount(mint.supply, mint.decimals),
+        decimals: mint.decimals,
+        is_initialized: mint.is_initialized,
+        freeze_authority: optional_key(mint.freeze_authority),
+        extensions: extensions(&state, Some(mint.decimals))?,
+    }))
+}
+
+fn field(name: &str, value: impl ToString) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+fn optional_field(name: &str, key: impl Into<Option<Pubkey>>) -> (String, String) {
+    let value = key.into().map(|key| key.to_string()).unwrap_or_else(|| "none".to_string());
+    field(name, value)
+}
+
+fn amount_field(name: &str, amount: u64, decimals: Option<u8>) -> (String, String) {
+    match decimals {
+        Some(decimals) => field(name, format_token_amount(amount, decimals)),
+        None => field(name, amount),
+    }
+}
+
+fn transfer_fee_fields(prefix: &str, fee: &TransferFee, decimals: Option<u8>) -> Vec<(String, String)> {
+    vec![
+        field(&format!("{} epoch", prefix), u64::from(fee.epoch)),
+        field(
+            &format!("{} fee basis points", prefix),
+            u16::from(fee.transfer_fee_basis_points),
+        ),
+        amount_field(&format!("{} maximum fee", prefix), u64::from(fee.maximum_fee), decimals),
+    ]
+}
+
+fn extensions<S: BaseState>(
+    state: &StateWithExtensions<S>,
+    decimals: Option<u8>,
+) -> Result<Vec<TokenExtension>, KeypairError> {
+    let types = state.get_extension_types().map_err(invalid)?;
+    types
+        .into_iter()
+        .map(|extension_type| {
+            let fields = match extension_type {
+                ExtensionType::TransferFeeConfig => {
+                    let config = state.get_extension::<TransferFeeConfig>().map_err(invalid)?;
+                    let mut fields = vec![
+                        optional_field("transfer fee config authority", config.transfer_fee_config_authority),
+                        optional_field("withdraw withheld authority", config.withdraw_withheld_authority),
+                        amount_field("withheld amount", u64::from(config.withheld_amount), decimals),
+                    ];
+                    fields.extend(transfer_fee_fields("newer", &config.newer_transfer_fee, decimals));
+                    fields.extend(transfer_fee_fields("older", &config.older_transfer_fee, decimals));
+                    fields
+                }
+                ExtensionType::TransferFeeAmount => {
+                    let amount = state.get_extension::<TransferFeeAmount>().map_err(invalid)?;
+                    vec![amount_field("withheld amount", u64::from(amount.withheld_amount), decimals)]
+                }
+                ExtensionType::MintCloseAuthority => {
+                    let authority = state.get_extension::<MintCloseAuthority>().map_err(invalid)?;
+                    vec![optional_field("close authority", authority.close_authority)]
+                }
+                ExtensionType::DefaultAccountState => {
+                    let default = state.get_extension::<DefaultAccountState>().map_err(invalid)?;
+                    let name = match AccountState::try_from(default.state) {
+                        Ok(AccountState::Initialized) => "initialized".to_string(),
+                        Ok(AccountState::Frozen) => "frozen".to_string(),
+                        _ => default.state.to_string(),
+                    };
+                    vec![field("state", name)]
+                }
+                ExtensionType::MemoTransfer => {
+                    let memo = state.get_extension::<MemoTransfer>().map_err(invalid)?;
+                    vec![field(
+                        "require incoming transfer memos",
+                        bool::from(memo.require_incoming_transfer_memos),
+                    )]
+                }
+                ExtensionType::CpiGuard => {
+                    let guard = state.get_extension::<CpiGuard>().map_err(invalid)?;
+                    vec![field("lock cpi", bool::from(guard.lock_cpi))]
+                }
+                ExtensionType::InterestBearingConfig => {
+                    let config = state.get_extension::<InterestBearingConfig>().map_err(invalid)?;
+                    vec![
+                        optional_field("rate authority", config.rate_authority),
+                        field("current rate basis points", i16::from(config.current_rate)),
+                        field(
+                            "pre-update average rate basis points",
+                            i16::from(config.pre_update_average_rate),
+                        ),
+                        field("initialization timestamp", i64::from(config.initialization_timestamp)),
+                        field("last update timestamp", i64::from(config.last_update_timestamp)),
+                    ]
+                }
+                ExtensionType::PermanentDelegate => {
+                    let delegate = state.get_extension::<PermanentDelegate>().map_err(invalid)?;
+                    vec![optional_field("delegate", delegate.delegate)]
+                }
+                // The rest are flags or confidential state with nothing readable to show
+                _ => vec![],
+            };
+            Ok(TokenExtension {
+                name: format!("{:?}", extension_type),
+                fields,
+            })
+        })
+        .collect()
+}
+
+fn sol(lamports: u64) -> String {
+    lamports_to_sol(lamports).to_string()
+}
+
+// The fields every sell order layout shares, which are exactly those of the first one
+fn sell_order(version: u8, order: SellOrderV1, decimals: Option<u8>) -> SellOrderAccount {
+    SellOrderAccount {
+        version,
+        marketplace: None,
+        seller: order.seller.to_string(),
+        token_mint: order.token_mint.to_string(),
+        nonce: None,
+        amount: order.amount.to_string(),
+        ui_amount: decimals.map(|decimals| format_token_amount(order.amount, decimals)),
+        price_per_token: order.price_per_token.to_string(),
+        price_per_token_sol: sol(order.price_per_token),
+        created_at: order.created_at,
+        created_at_time: Utc
+            .timestamp_opt(order.created_at, 0)
+            .single()
+            .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        is_active: order.is_active,
+    }
+}
+
+// Marketplace, sell order and seller state accounts are allocated at exactly their Borsh size,
+// and so are the sell orders earlier versions of the program left behind
+pub fn decode_marketplace_account_data(data: &[u8], decimals: Option<u8>) -> Result<DecodedAccount, KeypairError> {
+    match data.len() {
+        Marketplace::LEN => {
+            let marketplace = Marketplace::try_from_slice(data).map_err(invalid)?;
+            Ok(DecodedAccount::Marketplace(MarketplaceAccount {
+                admin: marketplace.admin.to_string(),
+                fee_basis_points: marketplace.fee_percentage,
+                fee_percent: format!("{}%", format_token_amount(marketplace.fee_percentage as u64, 2)),
+                total_volume: marketplace.total_volume.to_string(),
+                total_volume_sol: sol(marketplace.total_volume),
+                total_fees_collected: marketplace.total_fees_collected.to_string(),
+                total_fees_collected_sol: sol(marketplace.total_fees_collected),
+                is_initialized: marketplace.is_initialized,
+            }))
+        }
+        SellOrder::LEN => {
+            let order = SellOrder::try_from_slice(data).map_err(invalid)?;
+            let fields = SellOrderV1 {
+                seller: order.seller,
+                token_mint: order.token_mint,
+                amount: order.amount,
+                price_per_token: order.price_per_token,
+                created_at: order.created_at,
+                is_active: order.is_active,
+            };
+            Ok(DecodedAccount::SellOrder(SellOrderAccount {
+                marketplace: Some(order.marketplace.to_string()),
+                nonce: Some(order.nonce.to_string()),
+                ..sell_order(3, fields, decimals)
+            }))
+        }
+        SellOrderV2::LEN => {
+            let order = SellOrderV2::try_from_slice(data).map_err(invalid)?;
+            let fields = SellOrderV1 {
+                seller: order.seller,
+                token_mint: order.token_mint,
+                amount: order.amount,
+                price_per_token: order.price_per_token,
+                created_at: order.created_at,
+                is_active: order.is_active,
+            };
+            Ok(DecodedAccount::SellOrder(SellOrderAccount {
+                marketplace: Some(order.marketplace.to_string()),
+                ..sell_order(2, fields, decimals)
+            }))
+        }
+        SellOrderV1::LEN => {
+            let order = SellOrderV1::try_from_slice(data).map_err(invalid)?;
+            Ok(DecodedAccount::SellOrder(sell_order(1, order, decimals)))
+        }
+        SellerState::LEN => {
+            let state = SellerState::try_from_slice(data).map_err(invalid)?;
+            Ok(DecodedAccount::SellerState(SellerStateAccount {
+                marketplace: state.marketplace.to_string(),
+                seller: state.seller.to_string(),
+                order_count: state.order_count.to_string(),
+            }))
+        }
+        other => Err(KeypairError::InvalidAccountData(format!(
+            "{} bytes is neither a marketplace ({}), a sell order ({}, or {} and {} for older layouts) nor a \
+             seller state ({})",
+            other,
+            Marketplace::LEN,
+            SellOrder::LEN,
+            SellOrderV1::LEN,
+            SellOrderV2::LEN,
+            SellerState::LEN
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+    use spl_token_2022::extension::immutable_owner::ImmutableOwner;
+    use spl_token_2022::extension::StateWithExtensionsMut;
+    use spl_token_2022::pod::OptionalNonZeroPubkey;
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn mint() -> Mint {
+        Mint {
+            mint_authority: COption::Some(key(1)),
+            supply: 1_500_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+    }
+
+    fn token_account() -> Account {
+        Account {
+            mint: key(2),
+            owner: key(3),
+            amount: 2_500_000,
+            delegate: COption::Some(key(4)),
+            state: AccountState::Frozen,
+            is_native: COption::None,
+            delegated_amount: 1_000,
+            close_authority: COption::None,
+        }
+    }
+
+    fn marketplace_options() -> AccountDecodeOptions {
+        AccountDecodeOptions { marketplace_program_id: Some(key(9)), decimals: Some(6) }
+    }
+
+    fn decode_marketplace(data: Vec<u8>) -> Result<DecodedAccount, KeypairError> {
+        decode_account(&base64::encode(data), &key(9).to_string(), &marketplace_options())
+    }
+
+    #[test]
+    fn decodes_token_mint_and_account() {
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(mint(), &mut data).unwrap();
+        let decoded = decode_account(&base64::encode(&data), &spl_token::id().to_string(), &Default::default());
+        let DecodedAccount::Mint(mint) = decoded.unwrap() else { panic!("expected a mint") };
+        assert_eq!(mint.token_program, "spl-token");
+        assert_eq!(mint.mint_authority, Some(key(1).to_string()));
+        assert_eq!((mint.supply.as_str(), mint.ui_supply.as_str()), ("1500000", "1.5"));
+        assert_eq!(mint.freeze_authority, None);
+        assert!(mint.extensions.is_empty());
+
+        let mut data = vec![0; Account::LEN];
+        Account::pack(token_account(), &mut data).unwrap();
+        let options = AccountDecodeOptions { decimals: Some(6), ..Default::default() };
+        let decoded = decode_account(&base64::encode(&data), &spl_token::id().to_string(), &options);
+        let DecodedAccount::TokenAccount(account) = decoded.unwrap() else { panic!("expected a token account") };
+        assert_eq!((account.mint, account.owner), (key(2).to_string(), key(3).to_string()));
+        assert_eq!((account.amount.as_str(), account.ui_amount.as_deref()), ("2500000", Some("2.5")));
+        assert_eq!(account.delegate, Some(key(4).to_string()));
+        assert_eq!((account.delegated_amount.as_str(), account.state.as_str()), ("1000", "frozen"));
+        assert_eq!(account.native_reserve, None);
+    }
+
+    #[test]
+    fn decodes_token_2022_mint_extensions() {
+        let types = [ExtensionType::MintCloseAuthority, ExtensionType::TransferFeeConfig];
+        let mut data = vec![0; ExtensionType::get_account_len::<Mint>(&types)];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        state.base = mint();
+        state.pack_base();
+        state.init_account_type().unwrap();
+        state.init_extension::<MintCloseAuthority>(true).unwrap().close_authority =
+            OptionalNonZeroPubkey::try_from(Some(key(5))).unwrap();
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.newer_transfer_fee.transfer_fee_basis_points = 50.into();
+        config.newer_transfer_fee.maximum_fee = 5_000_000.into();
+
+        let decoded = decode_token_account_data(&data, TokenProgram::Token2022, None).unwrap();
+        let DecodedAccount::Mint(mint) = decoded else { panic!("expected a mint") };
+        assert_eq!(mint.token_program, "spl-token-2022");
+        assert_eq!(mint.ui_supply, "1.5");
+
+        let extension = |name: &str, fields: &[(&str, &str)]| TokenExtension {
+            name: name.to_string(),
+            fields: fields.iter().map(|(name, value)| field(name, value)).collect(),
+        };
+        // Fee amounts use the mint's own decimals
+        assert_eq!(
+            mint.extensions,
+            vec![
+                extension("MintCloseAuthority", &[("close authority", &key(5).to_string())]),
+                extension(
+                    "TransferFeeConfig",
+                    &[
+                        ("transfer fee config authority", "none"),
+                        ("withdraw withheld authority", "none"),
+                        ("withheld amount", "0"),
+                        ("newer epoch", "0"),
+                        ("newer fee basis points", "50"),
+                        ("newer maximum fee", "5"),
+                        ("older epoch", "0"),
+                        ("older fee basis points", "0"),
+                        ("older maximum fee", "0"),
+                    ],
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_token_2022_account_extensions() {
+        let types = [ExtensionType::ImmutableOwner, ExtensionType::MemoTransfer];
+        let mut data = vec![0; ExtensionType::get_account_len::<Account>(&types)];
+        let mut state = StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut data).unwrap();
+        state.base = token_account();
+        state.pack_base();
+        state.init_account_type().unwrap();
+        state.init_extension::<ImmutableOwner>(true).unwrap();
+        state.init_extension::<MemoTransfer>(true).unwrap().require_incoming_transfer_memos = true.into();
+
+        let decoded = decode_token_account_data(&data, TokenProgram::Token2022, Some(6)).unwrap();
+        let DecodedAccount::TokenAccount(account) = decoded else { panic!("expected a token account") };
+        assert_eq!(account.ui_amount.as_deref(), Some("2.5"));
+        let extensions: Vec<(&str, Vec<(String, String)>)> = account
+            .extensions
+            .iter()
+            .map(|extension| (extension.name.as_str(), extension.fields.clone()))
+            .collect();
+        assert_eq!(
+            extensions,
+            vec![
+                ("ImmutableOwner", vec![]),
+                ("MemoTransfer", vec![field("require incoming transfer memos", true)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn dispatches_marketplace_accounts_by_length() {
+        let marketplace = Marketplace {
+            admin: key(1),
+            fee_percentage: 250,
+            total_volume: 3_000_000_000,
+            total_fees_collected: 75_000_000,
+            is_initialized: true,
+        };
+        let data = marketplace.try_to_vec().unwrap();
+        assert_eq!(data.len(), 51);
+        let DecodedAccount::Marketplace(decoded) = decode_marketplace(data).unwrap() else {
+            panic!("expected a marketplace")
+        };
+        assert_eq!((decoded.fee_basis_points, decoded.fee_percent.as_str()), (250, "2.5%"));
+        assert_eq!(decoded.total_volume_sol, "3");
+        assert_eq!(decoded.total_fees_collected_sol, "0.075");
+
+        let state = SellerState { marketplace: key(1), seller: key(2), order_count: 7 };
+        let data = state.try_to_vec().unwrap();
+        assert_eq!(data.len(), 72);
+        let DecodedAccount::SellerState(decoded) = decode_marketplace(data).unwrap() else {
+            panic!("expected a seller state")
+        };
+        assert_eq!((decoded.seller, decoded.order_count), (key(2).to_string(), "7".to_string()));
+
+        let order = SellOrder {
+            marketplace: key(1),
+            seller: key(2),
+            token_mint: key(3),
+            nonce: 4,
+            amount: 1_500_000,
+            price_per_token: 2_500_000,
+            created_at: 1_700_000_000,
+            is_active: true,
+        };
+        let data = order.try_to_vec().unwrap();
+        assert_eq!(data.len(), 129);
+        let DecodedAccount::SellOrder(decoded) = decode_marketplace(data).unwrap() else {
+            panic!("expected a sell order")
+        };
+        assert_eq!(decoded.version, 3);
+        assert_eq!((decoded.marketplace, decoded.nonce), (Some(key(1).to_string()), Some("4".to_string())));
+        assert_eq!(decoded.ui_amount.as_deref(), Some("1.5"));
+        assert_eq!(decoded.price_per_token_sol, "0.0025");
+        assert_eq!(decoded.created_at_time.as_deref(), Some("2023-11-14T22:13:20Z"));
+
+        for length in [0, 50, 130] {
+            assert!(matches!(decode_marketplace(vec![0; length]), Err(KeypairError::InvalidAccountData(_))));
+        }
+    }
+
+    #[test]
+    fn decodes_legacy_sell_orders() {
+        let v1 = SellOrderV1 {
+            seller: key(2),
+            token_mint: key(3),
+            amount: 1_500_000,
+            price_per_token: 2_500_000,
+            created_at: 1_700_000_000,
+            is_active: false,
+        };
+        let data = v1.try_to_vec().unwrap();
+        assert_eq!(data.len(), 89);
+        let DecodedAccount::SellOrder(decoded) = decode_marketplace(data).unwrap() else {
+            panic!("expected a sell order")
+        };
+        assert_eq!((decoded.version, decoded.marketplace, decoded.nonce), (1, None, None));
+        assert_eq!((decoded.seller, decoded.token_mint), (key(2).to_string(), key(3).to_string()));
+        assert_eq!((decoded.amount.as_str(), decoded.is_active), ("1500000", false));
+
+        let v2 = SellOrderV2 {
+            marketplace: key(1),
+            seller: key(2),
+            token_mint: key(3),
+            amount: 1_500_000,
+            price_per_token: 2_500_000,
+            created_at: 1_700_000_000,
+            is_active: true,
+        };
+        let data = v2.try_to_vec().unwrap();
+        assert_eq!(data.len(), 121);
+        let DecodedAccount::SellOrder(decoded) = decode_marketplace(data).unwrap() else {
+            panic!("expected a sell order")
+        };
+        assert_eq!(decoded.version, 2);
+        assert_eq!(decoded.marketplace, Some(key(1).to_string()));
+        assert_eq!(decoded.nonce, None);
+        assert_eq!((decoded.price_per_token.as_str(), decoded.is_active), ("2500000", true));
+
+        // Missing fields serialize as null for JavaScript
+        let json = serde_json::to_value(DecodedAccount::SellOrder(decoded)).unwrap();
+        assert_eq!(json["type"], "sellOrder");
+        assert_eq!(json["version"], 2);
+        assert!(json["nonce"].is_null());
+    }
+
+    #[test]
+    fn rejects_unknown_owner() {
+        let data = base64::encode(vec![0; 51]);
+        for options in [AccountDecodeOptions::default(), marketplace_options()] {
+            let result = decode_account(&data, &key(8).to_string(), &options);
+            assert!(matches!(result, Err(KeypairError::InvalidAccountData(_))));
+        }
+        // Marketplace accounts are only decoded when the program is known
+        let result = decode_account(&data, &key(9).to_string(), &AccountDecodeOptions::default());
+        assert!(matches!(result, Err(KeypairError::InvalidAccountData(_))));
+    }
+}
@@ -0,0 +1,129 @@
+// Vanity address grinder with the same flags as `solana-keygen grind`:
+//
+//   solana-grind --starts-with sol:1 --ends-with xyz:2 --ignore-case --num-threads 8
+//
+// Every match is written to `<PUBKEY>.json` in the current directory as soon as it is
+// found. Ctrl-C stops the search, keeping the matches already written; a second Ctrl-C
+// exits at once.
+use solana_keypair_generator::grind::{grind, GrindMatch, GrindOptions, GrindPattern};
+use solana_keypair_generator::keyfile::write_keypair_file;
+use solana_sdk::signature::Signer;
+use std::error::Error;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const USAGE: &str = "Usage: solana-grind [--starts-with PREFIX:COUNT]... [--ends-with SUFFIX:COUNT]... \
+[--starts-and-ends-with PREFIX:SUFFIX:COUNT]... [--ignore-case] [--num-threads N]";
+
+// Set from the SIGINT handler, so it has to be a static
+static CANCEL: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+fn cancel_on_ctrl_c() {
+    extern "C" fn handle_sigint(_: libc::c_int) {
+        CANCEL.store(true, Ordering::SeqCst);
+        // Both calls are async-signal-safe
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+    unsafe {
+        libc::signal(libc::SIGINT, handle_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+}
+
+// Without a handler Ctrl-C ends the process, which loses nothing as matches are already written
+#[cfg(not(unix))]
+fn cancel_on_ctrl_c() {}
+
+fn parse_count(count: &str) -> Result<u64, Box<dyn Error>> {
+    Ok(count.parse().map_err(|_| format!("Invalid count '{}'", count))?)
+}
+
+fn parse_args(args: &[String]) -> Result<GrindOptions, Box<dyn Error>> {
+    let mut options = GrindOptions::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--starts-with" => {
+                let (prefix, count) = value()?.split_once(':').ok_or("Expected PREFIX:COUNT")?;
+                options.patterns.push(GrindPattern::starts_with(prefix, parse_count(count)?));
+            }
+            "--ends-with" => {
+                let (suffix, count) = value()?.split_once(':').ok_or("Expected SUFFIX:COUNT")?;
+                options.patterns.push(GrindPattern::ends_with(suffix, parse_count(count)?));
+            }
+            "--starts-and-ends-with" => {
+                let parts: Vec<&str> = value()?.split(':').collect();
+                let [prefix, suffix, count] = parts[..] else {
+                    return Err("Expected PREFIX:SUFFIX:COUNT".into());
+                };
+                options
+                    .patterns
+                    .push(GrindPattern::starts_and_ends_with(prefix, suffix, parse_count(count)?));
+            }
+            "--ignore-case" => options.ignore_case = true,
+            "--num-threads" => {
+                let threads = value()?;
+                options.threads = threads.parse().map_err(|_| format!("Invalid thread count '{}'", threads))?;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            other => return Err(format!("Unknown argument '{}'", other).into()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = parse_args(&args)?;
+    cancel_on_ctrl_c();
+
+    eprintln!(
+        "Searching with {} threads for {} pattern(s){}",
+        options.threads,
+        options.patterns.len(),
+        if options.ignore_case { " (ignoring case)" } else { "" }
+    );
+
+    let on_match = |found: &GrindMatch| {
+        let outfile = format!("{}.json", found.keypair.pubkey());
+        write_keypair_file(&found.keypair, &outfile, false)?;
+        println!("Wrote keypair to {}", outfile);
+        Ok(())
+    };
+
+    let matches = grind(&options, &CANCEL, |progress| {
+        let eta = progress
+            .estimated_time_remaining
+            .map(|eta| format!("~{}s", eta.as_secs()))
+            .unwrap_or_else(|| "unknown".to_string());
+        eprintln!(
+            "Searched {} keypairs in {}s ({:.0}/s), {} found, ETA {}",
+            progress.attempts,
+            progress.elapsed.as_secs(),
+            progress.attempts_per_second,
+            progress.found,
+            eta
+        );
+    }, on_match)?;
+
+    if CANCEL.load(Ordering::SeqCst) {
+        eprintln!("Cancelled after finding {} keypair(s)", matches.len());
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,87 @@
+// Show what a serialized transaction will do before signing or sending it:
+//
+//   solana-inspect <BASE64_TRANSACTION>
+//   solana-inspect --base58 --json <BASE58_TRANSACTION>
+//
+// Marketplace instructions are decoded at the program's declared id, or at the deployment given
+// with `--marketplace-program PROGRAM_ID`.
+// Pass `-` instead of a transaction to read it from stdin. Accounts loaded from address lookup
+// tables can be resolved with `--lookup-table TABLE=ADDRESS,ADDRESS,...`.
+use solana_keypair_generator::inspect::{inspect_transaction, InspectOptions};
+use solana_keypair_generator::parse_pubkey;
+use solana_keypair_generator::transaction::TransactionEncoding;
+use std::error::Error;
+use std::io::Read;
+use std::process;
+
+const USAGE: &str = "Usage: solana-inspect [--base58] [--json] [--marketplace-program PROGRAM_ID] \
+[--lookup-table TABLE=ADDRESS,...]... <TRANSACTION | ->";
+
+struct Args {
+    transaction: String,
+    encoding: TransactionEncoding,
+    json: bool,
+    options: InspectOptions,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, Box<dyn Error>> {
+    let mut transaction = None;
+    let mut encoding = TransactionEncoding::Base64;
+    let mut json = false;
+    let mut options = InspectOptions::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--base58" => encoding = TransactionEncoding::Base58,
+            "--base64" => encoding = TransactionEncoding::Base64,
+            "--json" => json = true,
+            "--marketplace-program" => options.marketplace_program_id = Some(parse_pubkey(value()?)?),
+            "--lookup-table" => {
+                let (table, addresses) = value()?.split_once('=').ok_or("Expected TABLE=ADDRESS,...")?;
+                let addresses = addresses
+                    .split(',')
+                    .map(parse_pubkey)
+                    .collect::<Result<Vec<_>, _>>()?;
+                options.lookup_tables.insert(parse_pubkey(table)?, addresses);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            other if transaction.is_none() && (other == "-" || !other.starts_with('-')) => {
+                transaction = Some(other.to_string());
+            }
+            other => return Err(format!("Unknown argument '{}'", other).into()),
+        }
+    }
+
+    let transaction = transaction.ok_or("Missing transaction")?;
+    Ok(Args { transaction, encoding, json, options })
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args = parse_args(&args)?;
+    if args.transaction == "-" {
+        args.transaction.clear();
+        std::io::stdin().read_to_string(&mut args.transaction)?;
+    }
+
+    let inspection = inspect_transaction(&args.transaction, args.encoding, &args.options)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&inspection)?);
+    } else {
+        print!("{}", inspection);
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::mnemonic::seed_from_mnemonic;
+use crate::KeypairError;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed, keypair_from_seed_and_derivation_path, Keypair, Signer};
+
+type HmacSha512 = Hmac<Sha512>;
+
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+// Derivation path layouts used by the common Solana wallets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivationScheme {
+    // m/44'/501'/{account}'/0' (Phantom, Solflare, `solana-keygen --derivation-path`)
+    Bip44Change,
+    // m/44'/501'/{account}'
+    Bip44,
+    // m/44'/501' (a single root address, account is ignored)
+    Bip44Root,
+    // m/501'/{account}'/0/0 (the deprecated Sollet derivation over secp256k1 BIP32)
+    LegacySollet,
+}
+
+impl DerivationScheme {
+    // Render the concrete path for an account index
+    pub fn path(&self, account: u32) -> String {
+        match self {
+            DerivationScheme::Bip44Change => format!("m/44'/501'/{}'/0'", account),
+            DerivationScheme::Bip44 => format!("m/44'/501'/{}'", account),
+            DerivationScheme::Bip44Root => "m/44'/501'".to_string(),
+            DerivationScheme::LegacySollet => format!("m/501'/{}'/0/0", account),
+        }
+    }
+}
+
+// An address found while scanning the accounts of a seed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedAddress {
+    pub account: u32,
+    pub path: String,
+    pub pubkey: Pubkey,
+}
+
+// Derive a keypair from a seed along any fully hardened SLIP-0010 path, e.g. "m/44'/501'/0'/0'"
+pub fn keypair_from_seed_and_path(seed: &[u8], path: &str) -> Result<Keypair, KeypairError> {
+    let indexes = parse_path(path)?;
+    if let Some(index) = indexes.iter().find(|index| *index & HARDENED_OFFSET == 0) {
+        return Err(KeypairError::InvalidDerivationPath(format!(
+            "non-hardened index {} in {}: ed25519 only supports hardened derivation",
+            index, path
+        )));
+    }
+
+    let derivation_path = DerivationPath::from_absolute_path_str(path)
+        .map_err(|e| KeypairError::InvalidDerivationPath(e.to_string()))?;
+    keypair_from_seed_and_derivation_path(seed, Some(derivation_path))
+        .map_err(|e| KeypairError::InvalidSecretKey(e.to_string()))
+}
+
+// Derive the keypair for an account index using one of the wallet schemes
+pub fn derive_keypair(seed: &[u8], scheme: DerivationScheme, account: u32) -> Result<Keypair, KeypairError> {
+    let path = scheme.path(account);
+    match scheme {
+        DerivationScheme::LegacySollet => {
+            let secret = bip32_secp256k1_derive(seed, &parse_path(&path)?)?;
+            keypair_from_seed(&secret).map_err(|e| KeypairError::InvalidSecretKey(e.to_string()))
+        }
+        _ => keypair_from_seed_and_path(seed, &path),
+    }
+}
+
+// Derive a keypair straight from a seed phrase and a hardened path
+pub fn keypair_from_mnemonic_and_path(
+    phrase: &str,
+    passphrase: &str,
+    path: &str,
+) -> Result<Keypair, KeypairError> {
+    let seed = seed_from_mnemonic(phrase, passphrase)?;
+    keypair_from_seed_and_path(&seed, path)
+}
+
+// List the first `count` addresses of a seed phrase under a scheme
+pub fn derive_addresses(
+    phrase: &str,
+    passphrase: &str,
+    scheme: DerivationScheme,
+    count: u32,
+) -> Result<Vec<DerivedAddress>, KeypairError> {
+    let seed = seed_from_mnemonic(phrase, passphrase)?;
+    // The root scheme only has a single address
+    let count = if scheme == DerivationScheme::Bip44Root { count.min(1) } else { count };
+
+    (0..count)
+        .map(|account| {
+            let keypair = derive_keypair(&seed, scheme, account)?;
+            Ok(DerivedAddress {
+                account,
+                path: scheme.path(account),
+                pubkey: keypair.pubkey(),
+            })
+        })
+        .collect()
+}
+
+// Parse "m/a'/b/c'" into raw child indexes, with the hardened bit set where marked
+fn parse_path(path: &str) -> Result<Vec<u32>, KeypairError> {
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+        return Err(KeypairError::InvalidDerivationPath(format!("{} must start with 'm'", path)));
+    }
+
+    parts
+        .map(|part| {
+            let (digits, hardened) = match part.strip_suffix('\'').or_else(|| part.strip_suffix('h')) {
+                Some(digits) => (digits, true),
+                None => (part, false),
+            };
+            let index: u32 = digits
+                .parse()
+                .map_err(|_| KeypairError::InvalidDerivationPath(format!("invalid component '{}' in {}", part, path)))?;
+            if index >= HARDENED_OFFSET {
+                return Err(KeypairError::InvalidDerivationPath(format!("index {} out of range in {}", index, path)));
+            }
+            Ok(if hardened { index | HARDENED_OFFSET } else { index })
+        })
+        .collect()
+}
+
+// BIP32 derivation over secp256k1, returning the 32-byte child private key.
+// Sollet fed this key to ed25519 as a seed, which is why it is only used for the legacy scheme.
+fn bip32_secp256k1_derive(seed: &[u8], indexes: &[u32]) -> Result<[u8; 32], KeypairError> {
+    let invalid_key = |e: libsecp256k1::Error| KeypairError::InvalidSecretKey(format!("{:?}", e));
+    let master = hmac_sha512(b"Bitcoin seed", &[seed]);
+    let mut key = libsecp256k1::SecretKey::parse_slice(&master[..32]).map_err(invalid_key)?;
+    let mut chain_code = master[32..].to_vec();
+
+    for index in indexes {
+        let data = if index & HARDENED_OFFSET != 0 {
+            [&[0u8][..], &key.serialize()[..]].concat()
+        } else {
+            libsecp256k1::PublicKey::from_secret_key(&key).serialize_compressed().to_vec()
+        };
+        let child = hmac_sha512(&chain_code, &[&data, &index.to_be_bytes()]);
+        let tweak = libsecp256k1::SecretKey::parse_slice(&child[..32]).map_err(invalid_key)?;
+        key.tweak_add_assign(&tweak).map_err(invalid_key)?;
+        chain_code = child[32..].to_vec();
+    }
+
+    Ok(key.serialize())
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Vec<u8> {
+    // HMAC accepts keys of any length
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC key");
+    for chunk in data {
+        mac.update(chunk);
+    }
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn addresses(scheme: DerivationScheme, count: u32) -> Vec<String> {
+        derive_addresses(PHRASE, "", scheme, count)
+            .unwrap()
+            .iter()
+            .map(|derived| derived.pubkey.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn slip10_matches_test_vector() {
+        // SLIP-0010 ed25519 test vector 1, chain m/0H
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let keypair = keypair_from_seed_and_path(&seed, "m/0'").unwrap();
+        assert_eq!(
+            hex::encode(keypair.secret().as_bytes()),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        assert_eq!(
+            hex::encode(keypair.pubkey().to_bytes()),
+            "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c"
+        );
+    }
+
+    #[test]
+    fn bip32_secp256k1_matches_test_vector() {
+        // BIP32 test vector 1, chain m/0H/1, which also covers a non-hardened step
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let key = bip32_secp256k1_derive(&seed, &[HARDENED_OFFSET, 1]).unwrap();
+        assert_eq!(hex::encode(key), "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368");
+    }
+
+    #[test]
+    fn bip44_change_matches_phantom() {
+        assert_eq!(
+            addresses(DerivationScheme::Bip44Change, 2),
+            ["HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk", "Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb"]
+        );
+        let keypair = keypair_from_mnemonic_and_path(PHRASE, "", "m/44'/501'/0'/0'").unwrap();
+        assert_eq!(keypair.pubkey().to_string(), "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk");
+    }
+
+    #[test]
+    fn bip44_matches_fixed_vector() {
+        assert_eq!(
+            addresses(DerivationScheme::Bip44, 2),
+            ["GjJyeC1r2RgkuoCWMyPYkCWSGSGLcz266EaAkLA27AhL", "ANf3TEKFL6jPWjzkndo4CbnNdUNkBk4KHPggJs2nu8Xi"]
+        );
+    }
+
+    #[test]
+    fn bip44_root_has_a_single_address() {
+        assert_eq!(addresses(DerivationScheme::Bip44Root, 5), ["D2PPQSYFe83nDzk96FqGumVU8JA7J8vj2Rhjc2oXzEi5"]);
+    }
+
+    #[test]
+    fn legacy_sollet_matches_fixed_vector() {
+        assert_eq!(
+            addresses(DerivationScheme::LegacySollet, 2),
+            ["FTt72dUEL1X9pPs9V9q4Eh5ejKXEbPD6snaHSQAmnJGU", "F1QLGD7iJPG7Lb9kzxd9JZ9QT3wtqkS6b7b5oXxpue99"]
+        );
+    }
+
+    #[test]
+    fn passphrase_is_part_of_the_seed() {
+        let derived = derive_addresses(PHRASE, "TREZOR", DerivationScheme::Bip44Change, 1).unwrap();
+        assert_eq!(derived[0].path, "m/44'/501'/0'/0'");
+        assert_eq!(derived[0].pubkey.to_string(), "7zSmbu6gKkb6HB7UDPtHYjwCWuBHU1D4TpNZFm4sndQe");
+    }
+
+    #[test]
+    fn rejects_non_hardened_ed25519_paths() {
+        let seed = seed_from_mnemonic(PHRASE, "").unwrap();
+        for path in ["m/44'/501'/0'/0", "m/44/501'", "m/501'/0'/0/0"] {
+            assert!(
+                matches!(keypair_from_seed_and_path(&seed, path), Err(KeypairError::InvalidDerivationPath(_))),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        let seed = seed_from_mnemonic(PHRASE, "").unwrap();
+        for path in ["44'/501'", "m/x'", "m/2147483648'", "m//0'"] {
+            assert!(
+                matches!(keypair_from_seed_and_path(&seed, path), Err(KeypairError::InvalidDerivationPath(_))),
+                "{}",
+                path
+            );
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::keystore::KeystoreError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeypairError {
+    #[error("Invalid base58 character '{character}' at position {position}")]
+    InvalidBase58Character { character: char, position: usize },
+    #[error("Invalid base58 input: {0}")]
+    InvalidBase58(String),
+    #[error("Invalid base64 byte 0x{byte:02x} at position {position}")]
+    InvalidBase64Byte { byte: u8, position: usize },
+    #[error("Invalid base64 input: {0}")]
+    InvalidBase64(String),
+    #[error("Invalid hex character '{character}' at position {position}")]
+    InvalidHexCharacter { character: char, position: usize },
+    #[error("Invalid hex input: {0}")]
+    InvalidHex(String),
+    #[error("Invalid key length {actual}: expected {expected} bytes")]
+    InvalidLength { expected: &'static str, actual: usize },
+    #[error("Invalid secret key: {0}")]
+    InvalidSecretKey(String),
+    #[error("Embedded pubkey {embedded} does not match the secret key's pubkey {derived}")]
+    PubkeyMismatch { embedded: String, derived: String },
+    #[error("Invalid pubkey: {0}")]
+    InvalidPubkey(String),
+    #[error("Account owner {0} is not a token program")]
+    UnsupportedTokenProgram(String),
+    #[error("Invalid JSON key: {0}")]
+    InvalidJson(String),
+    #[error("Unrecognized secret key format: {0}")]
+    UnrecognizedSecretFormat(String),
+    #[error("Ambiguous secret key: it decodes to different keypairs as {0}")]
+    AmbiguousSecretFormat(String),
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+    #[error("Signature verification failed")]
+    SignatureVerificationFailed,
+    #[error("Invalid sign-in message: {0}")]
+    InvalidSignInMessage(String),
+    #[error("Sign-in rejected: {0}")]
+    SignInRejected(String),
+    #[error("Invalid blockhash {0}")]
+    InvalidBlockhash(String),
+    #[error("Invalid instruction: {0}")]
+    InvalidInstruction(String),
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("Signing failed: {0}")]
+    SigningFailed(String),
+    #[error("Invalid nonce account: {0}")]
+    InvalidNonceAccount(String),
+    #[error("Invalid address lookup table: {0}")]
+    InvalidLookupTable(String),
+    #[error("Invalid account data: {0}")]
+    InvalidAccountData(String),
+    #[error("Invalid seed: {0}")]
+    InvalidSeed(String),
+    #[error("Invalid secret share: {0}")]
+    InvalidShare(String),
+    #[error("Recovered pubkey {recovered} does not match the expected {expected}")]
+    RecoveredPubkeyMismatch { expected: String, recovered: String },
+    #[error("Invalid seed phrase: {0}")]
+    InvalidMnemonic(String),
+    #[error("Unsupported word count {0}: expected 12 or 24")]
+    UnsupportedWordCount(usize),
+    #[error("Invalid derivation path: {0}")]
+    InvalidDerivationPath(String),
+    #[error("Character '{character}' at position {position} of '{pattern}' can never appear in a base58 pubkey")]
+    InvalidPatternCharacter { pattern: String, character: char, position: usize },
+    #[error("Invalid grind options: {0}")]
+    InvalidGrindOptions(String),
+    #[error("Refusing to overwrite existing keypair file {0}")]
+    KeypairFileExists(String),
+    #[error("I/O error on {path}: {message}")]
+    Io { path: String, message: String },
+    #[error(transparent)]
+    Keystore(#[from] KeystoreError),
+}
+
+impl From<bs58::decode::Error> for KeypairError {
+    fn from(e: bs58::decode::Error) -> Self {
+        match e {
+            bs58::decode::Error::InvalidCharacter { character, index } => {
+                KeypairError::InvalidBase58Character { character, position: index }
+            }
+            other => KeypairError::InvalidBase58(other.to_string()),
+        }
+    }
+}
+
+impl From<base64::DecodeError> for KeypairError {
+    fn from(e: base64::DecodeError) -> Self {
+        match e {
+            base64::DecodeError::InvalidByte(position, byte) | base64::DecodeError::InvalidLastSymbol(position, byte) => {
+                KeypairError::InvalidBase64Byte { byte, position }
+            }
+            other => KeypairError::InvalidBase64(other.to_string()),
+        }
+    }
+}
+
+impl From<hex::FromHexError> for KeypairError {
+    fn from(e: hex::FromHexError) -> Self {
+        match e {
+            hex::FromHexError::InvalidHexCharacter { c, index } => {
+                KeypairError::InvalidHexCharacter { character: c, position: index }
+            }
+            other => KeypairError::InvalidHex(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// Serializable keypair export matching the response of pages/api/generate-keypair.ts
+use crate::keyfile::SecretKeyFormat;
+use crate::mnemonic::generate_keypair_with_mnemonic;
+use crate::{generate_keypair, KeypairError};
+use base64::encode;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, Signer};
+
+// Only the encodings the caller asked for are filled in and serialized
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeypairExport {
+    pub public_key: String,
+    // Base58 secret key, as Phantom and web3.js export it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<String>,
+    // Same value as `secret_key`; the web API has always returned both
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keypair_base58: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_key_base64: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_key_hex: Option<String>,
+    // The id.json byte array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key_array: Option<Vec<u8>>,
+    // Always present, null unless the keypair came from a seed phrase
+    pub mnemonic: Option<String>,
+}
+
+impl KeypairExport {
+    // Export a keypair in the requested encodings
+    pub fn new(keypair: &Keypair, formats: &[SecretKeyFormat]) -> Self {
+        let bytes = keypair.to_bytes();
+        let mut export = KeypairExport {
+            public_key: keypair.pubkey().to_string(),
+            ..KeypairExport::default()
+        };
+
+        for format in formats {
+            match format {
+                SecretKeyFormat::Base58 => {
+                    let base58 = keypair.to_base58_string();
+                    export.secret_key = Some(base58.clone());
+                    export.keypair_base58 = Some(base58);
+                }
+                SecretKeyFormat::Base64 => export.secret_key_base64 = Some(encode(bytes)),
+                SecretKeyFormat::Hex => export.secret_key_hex = Some(hex::encode(bytes)),
+                SecretKeyFormat::JsonArray => export.private_key_array = Some(bytes.to_vec()),
+            }
+        }
+        export
+    }
+
+    pub fn with_mnemonic(mut self, phrase: &str) -> Self {
+        self.mnemonic = Some(phrase.to_string());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("keypair export serializes")
+    }
+}
+
+// The encodings the web API currently returns
+pub const WEB_API_FORMATS: [SecretKeyFormat; 2] = [SecretKeyFormat::Base58, SecretKeyFormat::JsonArray];
+
+// Generate a new keypair and export it in the requested encodings
+pub fn generate_keypair_export(formats: &[SecretKeyFormat]) -> KeypairExport {
+    KeypairExport::new(&generate_keypair(), formats)
+}
+
+// Generate a keypair from a fresh seed phrase and export both
+pub fn generate_keypair_export_with_mnemonic(
+    word_count: usize,
+    passphrase: &str,
+    formats: &[SecretKeyFormat],
+) -> Result<KeypairExport, KeypairError> {
+    let (phrase, keypair) = generate_keypair_with_mnemonic(word_count, passphrase)?;
+    Ok(KeypairExport::new(&keypair, formats).with_mnemonic(&phrase))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_sdk::signature::keypair_from_seed;
+
+    const PUBKEY: &str = "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9";
+    const SECRET_BASE58: &str =
+        "2AXDGYSE4f2sz7tvMMzyHvUfcoJmxudvdhBcmiUSo6iuCXagjUCKEQF21awZnUGxmwD4m9vGXuC3qieHXJQHAcT";
+    const SECRET_BASE64: &str =
+        "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQGKiOPddAnxlf1S2y08ul1yymcJvx2UEhvzdIgBtA9vXA==";
+    const PUBKEY_BYTES: [u8; 32] = [
+        138, 136, 227, 221, 116, 9, 241, 149, 253, 82, 219, 45, 60, 186, 93, 114, 202, 103, 9, 191, 29, 148, 18, 27,
+        243, 116, 136, 1, 180, 15, 111, 92,
+    ];
+
+    fn export(formats: &[SecretKeyFormat]) -> serde_json::Value {
+        let keypair = keypair_from_seed(&[1; 32]).unwrap();
+        serde_json::to_value(KeypairExport::new(&keypair, formats)).unwrap()
+    }
+
+    #[test]
+    fn serializes_each_encoding_under_its_web_api_name() {
+        assert_eq!(export(&[]), json!({ "publicKey": PUBKEY, "mnemonic": null }));
+        assert_eq!(
+            export(&[SecretKeyFormat::Base58]),
+            json!({
+                "publicKey": PUBKEY,
+                "secretKey": SECRET_BASE58,
+                "keypairBase58": SECRET_BASE58,
+                "mnemonic": null,
+            })
+        );
+        assert_eq!(
+            export(&[SecretKeyFormat::Base64]),
+            json!({
+                "publicKey": PUBKEY,
+                "secretKeyBase64": SECRET_BASE64,
+                "mnemonic": null,
+            })
+        );
+        assert_eq!(
+            export(&[SecretKeyFormat::Hex]),
+            json!({
+                "publicKey": PUBKEY,
+                "secretKeyHex": format!("{}{}", "01".repeat(32), hex::encode(PUBKEY_BYTES)),
+                "mnemonic": null,
+            })
+        );
+        let array: Vec<u8> = [[1; 32], PUBKEY_BYTES].concat();
+        assert_eq!(
+            export(&[SecretKeyFormat::JsonArray]),
+            json!({ "publicKey": PUBKEY, "privateKeyArray": array, "mnemonic": null })
+        );
+    }
+
+    #[test]
+    fn matches_the_web_api_response() {
+        let keypair = keypair_from_seed(&[1; 32]).unwrap();
+        let export = KeypairExport::new(&keypair, &WEB_API_FORMATS).with_mnemonic("abandon ability able");
+        let array: Vec<u8> = [[1; 32], PUBKEY_BYTES].concat();
+        assert_eq!(
+            serde_json::to_value(&export).unwrap(),
+            json!({
+                "publicKey": PUBKEY,
+                "secretKey": SECRET_BASE58,
+                "keypairBase58": SECRET_BASE58,
+                "privateKeyArray": array,
+                "mnemonic": "abandon ability able",
+            })
+        );
+        assert_eq!(serde_json::from_str::<KeypairExport>(&export.to_json()).unwrap(), export);
+    }
+}
@@ -0,0 +1,429 @@
+use crate::{generate_keypair, KeypairError};
+use solana_sdk::signature::{Keypair, Signer};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// Longest base58 string a 32-byte pubkey can encode to
+const MAX_PUBKEY_LENGTH: usize = 44;
+
+// Attempts a worker makes before publishing them to the shared counter
+const ATTEMPT_BATCH: u64 = 1_000;
+
+// A vanity pattern and how many matching keypairs to find for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrindPattern {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    pub count: u64,
+}
+
+impl GrindPattern {
+    pub fn starts_with(prefix: &str, count: u64) -> Self {
+        GrindPattern { prefix: Some(prefix.to_string()), suffix: None, count }
+    }
+
+    pub fn ends_with(suffix: &str, count: u64) -> Self {
+        GrindPattern { prefix: None, suffix: Some(suffix.to_string()), count }
+    }
+
+    pub fn starts_and_ends_with(prefix: &str, suffix: &str, count: u64) -> Self {
+        GrindPattern {
+            prefix: Some(prefix.to_string()),
+            suffix: Some(suffix.to_string()),
+            count,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GrindOptions {
+    pub patterns: Vec<GrindPattern>,
+    pub ignore_case: bool,
+    pub threads: usize,
+    // How often the progress callback is invoked
+    pub progress_interval: Duration,
+}
+
+impl Default for GrindOptions {
+    fn default() -> Self {
+        GrindOptions {
+            patterns: Vec::new(),
+            ignore_case: false,
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            progress_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+// Snapshot handed to the progress callback
+#[derive(Debug, Clone)]
+pub struct GrindProgress {
+    pub attempts: u64,
+    pub found: u64,
+    pub elapsed: Duration,
+    pub attempts_per_second: f64,
+    // None until a rate is known or once every pattern is satisfied
+    pub estimated_time_remaining: Option<Duration>,
+}
+
+// A keypair found for the pattern at `pattern_index` in the options
+pub struct GrindMatch {
+    pub pattern_index: usize,
+    pub keypair: Keypair,
+}
+
+// Check a prefix or suffix against the base58 alphabet so impossible patterns fail fast
+pub fn validate_pattern(pattern: &str, ignore_case: bool) -> Result<(), KeypairError> {
+    if pattern.is_empty() {
+        return Err(KeypairError::InvalidGrindOptions("pattern must not be empty".to_string()));
+    }
+    if pattern.len() > MAX_PUBKEY_LENGTH {
+        return Err(KeypairError::InvalidGrindOptions(format!(
+            "pattern '{}' is longer than a pubkey ({} characters)",
+            pattern, MAX_PUBKEY_LENGTH
+        )));
+    }
+
+    for (position, c) in pattern.chars().enumerate() {
+        let possible = if ignore_case {
+            BASE58_ALPHABET.chars().any(|a| a.eq_ignore_ascii_case(&c))
+        } else {
+            BASE58_ALPHABET.contains(c)
+        };
+        if !possible {
+            return Err(KeypairError::InvalidPatternCharacter {
+                pattern: pattern.to_string(),
+                character: c,
+                position,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Expected number of attempts needed to hit a pattern once
+pub fn expected_attempts(pattern: &GrindPattern, ignore_case: bool) -> f64 {
+    let chars = pattern
+        .prefix
+        .iter()
+        .chain(pattern.suffix.iter())
+        .flat_map(|part| part.chars());
+
+    chars
+        .map(|c| {
+            let matches = if ignore_case {
+                BASE58_ALPHABET.chars().filter(|a| a.eq_ignore_ascii_case(&c)).count()
+            } else {
+                1
+            };
+            BASE58_ALPHABET.len() as f64 / matches.max(1) as f64
+        })
+        .product()
+}
+
+// Search random keypairs for pubkeys matching the patterns, like `solana-keygen grind`.
+// Each match is handed to `on_match` as soon as it is found; an error from it stops the
+// search and is returned. Setting `cancel` stops the workers and returns whatever was
+// found so far.
+pub fn grind<P, M>(
+    options: &GrindOptions,
+    cancel: &AtomicBool,
+    mut on_progress: P,
+    mut on_match: M,
+) -> Result<Vec<GrindMatch>, KeypairError>
+where
+    P: FnMut(&GrindProgress),
+    M: FnMut(&GrindMatch) -> Result<(), KeypairError>,
+{
+    if options.patterns.is_empty() {
+        return Err(KeypairError::InvalidGrindOptions("at least one pattern is required".to_string()));
+    }
+    if options.threads == 0 {
+        return Err(KeypairError::InvalidGrindOptions("thread count must be at least 1".to_string()));
+    }
+    for pattern in &options.patterns {
+        if pattern.prefix.is_none() && pattern.suffix.is_none() {
+            return Err(KeypairError::InvalidGrindOptions("pattern needs a prefix or a suffix".to_string()));
+        }
+        for part in pattern.prefix.iter().chain(pattern.suffix.iter()) {
+            validate_pattern(part, options.ignore_case)?;
+        }
+    }
+
+    let patterns: Vec<(Option<String>, Option<String>)> = options
+        .patterns
+        .iter()
+        .map(|pattern| {
+            let normalize = |part: &String| {
+                if options.ignore_case {
+                    part.to_lowercase()
+                } else {
+                    part.clone()
+                }
+            };
+            (pattern.prefix.as_ref().map(normalize), pattern.suffix.as_ref().map(normalize))
+        })
+        .collect();
+
+    let remaining: Vec<AtomicU64> = options.patterns.iter().map(|p| AtomicU64::new(p.count)).collect();
+    let attempts = AtomicU64::new(0);
+    let done = AtomicBool::new(remaining.iter().all(|r| r.load(Ordering::Relaxed) == 0));
+    let start = Instant::now();
+
+    let mut matches = Vec::new();
+    let mut outcome = Ok(());
+    thread::scope(|scope| {
+        let (patterns, remaining, attempts, done) = (&patterns, &remaining, &attempts, &done);
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..options.threads {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                let mut local_attempts = 0;
+                while !done.load(Ordering::Relaxed) && !cancel.load(Ordering::Relaxed) {
+                    let keypair = generate_keypair();
+                    let pubkey = keypair.pubkey().to_string();
+                    let pubkey = if options.ignore_case { pubkey.to_lowercase() } else { pubkey };
+
+                    local_attempts += 1;
+                    if local_attempts == ATTEMPT_BATCH {
+                        attempts.fetch_add(local_attempts, Ordering::Relaxed);
+                        local_attempts = 0;
+                    }
+
+                    // Claim a slot for the first matching pattern another worker has not filled yet
+                    let claimed = patterns.iter().zip(remaining).position(|((prefix, suffix), left)| {
+                        prefix.as_ref().map_or(true, |p| pubkey.starts_with(p.as_str()))
+                            && suffix.as_ref().map_or(true, |s| pubkey.ends_with(s.as_str()))
+                            && left
+                                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |r| r.checked_sub(1))
+                                .is_ok()
+                    });
+                    let Some(pattern_index) = claimed else { continue };
+
+                    // The receiver outlives every worker, so sending cannot fail
+                    let _ = sender.send(GrindMatch { pattern_index, keypair });
+                    if remaining.iter().all(|r| r.load(Ordering::SeqCst) == 0) {
+                        done.store(true, Ordering::SeqCst);
+                    }
+                }
+                attempts.fetch_add(local_attempts, Ordering::Relaxed);
+            });
+        }
+
+        drop(sender);
+
+        // Once stopping, wait for the workers to hand over their last matches and exit
+        let poll = options.progress_interval.min(Duration::from_millis(50));
+        let mut last_report = Instant::now();
+        loop {
+            let stopping = done.load(Ordering::Relaxed) || cancel.load(Ordering::Relaxed);
+            let received = if stopping {
+                receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                receiver.recv_timeout(poll)
+            };
+            match received {
+                Ok(found) => {
+                    if outcome.is_ok() {
+                        outcome = on_match(&found);
+                        if outcome.is_err() {
+                            done.store(true, Ordering::SeqCst);
+                        }
+                    }
+                    matches.push(found);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if !stopping && last_report.elapsed() >= options.progress_interval {
+                last_report = Instant::now();
+                let found = matches.len() as u64;
+                on_progress(&progress(options, remaining, attempts.load(Ordering::Relaxed), found, start.elapsed()));
+            }
+        }
+    });
+
+    outcome.map(|()| matches)
+}
+
+fn progress(
+    options: &GrindOptions,
+    remaining: &[AtomicU64],
+    attempts: u64,
+    found: u64,
+    elapsed: Duration,
+) -> GrindProgress {
+    let attempts_per_second = if elapsed.as_secs_f64() > 0.0 {
+        attempts as f64 / elapsed.as_secs_f64()
+    } else {
+        0.0
+    };
+
+    // Patterns are searched in parallel, so the slowest one bounds the estimate
+    let attempts_needed = options
+        .patterns
+        .iter()
+        .zip(remaining)
+        .map(|(pattern, left)| left.load(Ordering::Relaxed) as f64 * expected_attempts(pattern, options.ignore_case))
+        .fold(0.0, f64::max);
+
+    // Very long patterns can exceed what a Duration holds, which also reads as "unknown"
+    let estimated_time_remaining = if attempts_per_second > 0.0 && attempts_needed > 0.0 {
+        Duration::try_from_secs_f64(attempts_needed / attempts_per_second).ok()
+    } else {
+        None
+    };
+
+    GrindProgress {
+        attempts,
+        found,
+        elapsed,
+        attempts_per_second,
+        estimated_time_remaining,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(patterns: Vec<GrindPattern>) -> GrindOptions {
+        GrindOptions {
+            patterns,
+            threads: 2,
+            progress_interval: Duration::from_millis(10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_pattern_rejects_impossible_patterns() {
+        assert!(validate_pattern("So1ana", false).is_ok());
+
+        // '0' is not in the base58 alphabet in either case
+        match validate_pattern("abc0", true) {
+            Err(KeypairError::InvalidPatternCharacter { character, position, .. }) => {
+                assert_eq!((character, position), ('0', 3));
+            }
+            other => panic!("unexpected result {:?}", other.err()),
+        }
+
+        let too_long = "a".repeat(MAX_PUBKEY_LENGTH + 1);
+        assert!(matches!(validate_pattern(&too_long, false), Err(KeypairError::InvalidGrindOptions(_))));
+        assert!(validate_pattern(&too_long[1..], false).is_ok());
+        assert!(matches!(validate_pattern("", false), Err(KeypairError::InvalidGrindOptions(_))));
+    }
+
+    #[test]
+    fn validate_pattern_accepts_other_case_when_ignoring_case() {
+        // Lowercase 'l' is excluded from base58, but 'L' is not
+        assert!(matches!(validate_pattern("l", false), Err(KeypairError::InvalidPatternCharacter { .. })));
+        assert!(validate_pattern("l", true).is_ok());
+        assert!(validate_pattern("O", true).is_ok());
+        assert!(validate_pattern("I", false).is_err());
+    }
+
+    #[test]
+    fn expected_attempts_follows_the_alphabet() {
+        assert_eq!(expected_attempts(&GrindPattern::starts_with("a", 1), false), 58.0);
+        assert_eq!(expected_attempts(&GrindPattern::starts_with("ab", 1), false), 58.0 * 58.0);
+        assert_eq!(expected_attempts(&GrindPattern::starts_and_ends_with("a", "b", 1), false), 58.0 * 58.0);
+
+        // Letters in both cases match twice as often, digits and single-case letters do not
+        assert_eq!(expected_attempts(&GrindPattern::ends_with("a", 1), true), 29.0);
+        assert_eq!(expected_attempts(&GrindPattern::ends_with("2", 1), true), 58.0);
+        assert_eq!(expected_attempts(&GrindPattern::ends_with("l", 1), true), 58.0);
+    }
+
+    #[test]
+    fn grind_finds_count_matches_for_one_char_prefix() {
+        let options = options(vec![GrindPattern::starts_with("a", 3)]);
+        let mut reported = Vec::new();
+        let matches = grind(&options, &AtomicBool::new(false), |_| {}, |found| {
+            reported.push(found.keypair.pubkey());
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(matches.len(), 3);
+        for found in &matches {
+            assert_eq!(found.pattern_index, 0);
+            assert!(found.keypair.pubkey().to_string().starts_with('a'));
+        }
+        let found: Vec<_> = matches.iter().map(|found| found.keypair.pubkey()).collect();
+        assert_eq!(reported, found);
+    }
+
+    #[test]
+    fn grind_fills_each_pattern_with_ignore_case() {
+        let mut options = options(vec![GrindPattern::starts_with("a", 2), GrindPattern::ends_with("b", 1)]);
+        options.ignore_case = true;
+        let matches = grind(&options, &AtomicBool::new(false), |_| {}, |_| Ok(())).unwrap();
+
+        assert_eq!(matches.iter().filter(|found| found.pattern_index == 0).count(), 2);
+        assert_eq!(matches.iter().filter(|found| found.pattern_index == 1).count(), 1);
+        for found in &matches {
+            let pubkey = found.keypair.pubkey().to_string().to_lowercase();
+            assert!(if found.pattern_index == 0 { pubkey.starts_with('a') } else { pubkey.ends_with('b') });
+        }
+    }
+
+    #[test]
+    fn grind_returns_early_when_cancelled() {
+        // Effectively impossible to find, so only cancelling ends the search
+        let options = options(vec![GrindPattern::starts_with("aaaaaaaaaa", 1)]);
+        let cancel = AtomicBool::new(true);
+        let matches = grind(&options, &cancel, |_| {}, |_| Ok(())).unwrap();
+        assert!(matches.is_empty());
+
+        let cancel = AtomicBool::new(false);
+        let mut reports = 0;
+        let matches = grind(
+            &options,
+            &cancel,
+            |progress| {
+                reports += 1;
+                assert_eq!(progress.found, 0);
+                cancel.store(true, Ordering::SeqCst);
+            },
+            |_| Ok(()),
+        )
+        .unwrap();
+        assert!(matches.is_empty());
+        assert_eq!(reports, 1);
+    }
+
+    #[test]
+    fn grind_stops_on_match_error() {
+        let options = options(vec![GrindPattern::starts_with("a", 5)]);
+        let mut calls = 0;
+        let result = grind(&options, &AtomicBool::new(false), |_| {}, |_| {
+            calls += 1;
+            Err(KeypairError::InvalidGrindOptions("disk full".to_string()))
+        });
+        assert!(matches!(result, Err(KeypairError::InvalidGrindOptions(message)) if message == "disk full"));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn grind_rejects_invalid_options() {
+        let cancel = AtomicBool::new(false);
+        let mut no_threads = options(vec![GrindPattern::starts_with("a", 1)]);
+        no_threads.threads = 0;
+        let no_patterns = options(Vec::new());
+        let neither = options(vec![GrindPattern { prefix: None, suffix: None, count: 1 }]);
+        let bad_char = options(vec![GrindPattern::ends_with("0", 1)]);
+
+        for options in [no_threads, no_patterns, neither] {
+            let result = grind(&options, &cancel, |_| {}, |_| Ok(()));
+            assert!(matches!(result, Err(KeypairError::InvalidGrindOptions(_))));
+        }
+        let result = grind(&bad_char, &cancel, |_| {}, |_| Ok(()));
+        assert!(matches!(result, Err(KeypairError::InvalidPatternCharacter { .. })));
+    }
+}
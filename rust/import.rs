@@ -0,0 +1,241 @@
+// Universal secret key import: work out how a pasted key is encoded and decode it
+use crate::keyfile::{keypair_from_format, SecretKeyFormat};
+use crate::mnemonic::keypair_from_mnemonic;
+use crate::KeypairError;
+use solana_sdk::signature::{Keypair, Signer};
+use std::fmt;
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// The shape an imported secret was recognised as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedFormat {
+    JsonArray,
+    Base58,
+    Base64,
+    Hex,
+    Mnemonic,
+}
+
+impl DetectedFormat {
+    // The matching encoding for re-exporting, if the input was an encoded key
+    pub fn key_format(&self) -> Option<SecretKeyFormat> {
+        match self {
+            DetectedFormat::JsonArray => Some(SecretKeyFormat::JsonArray),
+            DetectedFormat::Base58 => Some(SecretKeyFormat::Base58),
+            DetectedFormat::Base64 => Some(SecretKeyFormat::Base64),
+            DetectedFormat::Hex => Some(SecretKeyFormat::Hex),
+            DetectedFormat::Mnemonic => None,
+        }
+    }
+}
+
+impl fmt::Display for DetectedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.key_format() {
+            Some(format) => write!(f, "{}", format),
+            None => write!(f, "seed phrase"),
+        }
+    }
+}
+
+pub struct ImportedKeypair {
+    pub format: DetectedFormat,
+    pub keypair: Keypair,
+}
+
+// Import a secret pasted in any supported encoding (no BIP39 passphrase)
+pub fn import_secret(input: &str) -> Result<ImportedKeypair, KeypairError> {
+    import_secret_with_passphrase(input, "")
+}
+
+// Import a secret, using `passphrase` if the input turns out to be a seed phrase.
+// Seed phrases recover like `solana-keygen recover` without a derivation path; use
+// `derivation::derive_addresses` to find wallet accounts such as Phantom's.
+//
+// Every encoding the input could plausibly be is tried. Decoded keys must have an embedded
+// pubkey matching their secret half, which rules out accidental decodes, and inputs that still
+// decode to different keypairs are rejected as ambiguous.
+pub fn import_secret_with_passphrase(input: &str, passphrase: &str) -> Result<ImportedKeypair, KeypairError> {
+    let input = input.trim();
+    let candidates = plausible_formats(input);
+    if candidates.is_empty() {
+        return Err(KeypairError::UnrecognizedSecretFormat(
+            "expected a JSON byte array, base58, base64, hex or a seed phrase".to_string(),
+        ));
+    }
+
+    let mut decoded: Vec<ImportedKeypair> = Vec::new();
+    let mut errors: Vec<(DetectedFormat, KeypairError)> = Vec::new();
+    for format in candidates {
+        let result = match format.key_format() {
+            Some(key_format) => keypair_from_format(input, key_format),
+            None => keypair_from_mnemonic(&normalize_phrase(input), passphrase),
+        };
+        match result {
+            Ok(keypair) => decoded.push(ImportedKeypair { format, keypair }),
+            Err(err) => errors.push((format, err)),
+        }
+    }
+
+    if !decoded.is_empty() {
+        return agreed_keypair(decoded);
+    }
+
+    // Only one reading made sense, so its own error is the most useful one
+    if errors.len() == 1 {
+        return Err(errors.remove(0).1);
+    }
+    let reasons: Vec<String> = errors
+        .iter()
+        .map(|(format, err)| format!("as {}: {}", format, err))
+        .collect();
+    Err(KeypairError::UnrecognizedSecretFormat(reasons.join("; ")))
+}
+
+// The keypair every successful decode agrees on
+fn agreed_keypair(mut decoded: Vec<ImportedKeypair>) -> Result<ImportedKeypair, KeypairError> {
+    let pubkey = decoded[0].keypair.pubkey();
+    if decoded.iter().any(|other| other.keypair.pubkey() != pubkey) {
+        let formats: Vec<String> = decoded.iter().map(|d| d.format.to_string()).collect();
+        return Err(KeypairError::AmbiguousSecretFormat(formats.join(" and ")));
+    }
+    Ok(decoded.swap_remove(0))
+}
+
+// Formats whose character set and shape the input fits, most specific first
+fn plausible_formats(input: &str) -> Vec<DetectedFormat> {
+    if input.starts_with('[') {
+        return vec![DetectedFormat::JsonArray];
+    }
+
+    let words: Vec<&str> = input.split_whitespace().collect();
+    if words.len() > 1 {
+        let is_phrase = words.iter().all(|word| word.chars().all(|c| c.is_ascii_alphabetic()));
+        return if is_phrase { vec![DetectedFormat::Mnemonic] } else { Vec::new() };
+    }
+
+    let mut formats = Vec::new();
+    let digits = input.strip_prefix("0x").unwrap_or(input);
+    if !digits.is_empty() && digits.len() % 2 == 0 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        formats.push(DetectedFormat::Hex);
+    }
+    if !input.is_empty() && input.chars().all(|c| BASE58_ALPHABET.contains(c)) {
+        formats.push(DetectedFormat::Base58);
+    }
+    if !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '=') {
+        formats.push(DetectedFormat::Base64);
+    }
+    formats
+}
+
+// Seed phrases are often pasted with odd spacing or capitalisation
+fn normalize_phrase(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::keypair_from_seed;
+
+    // The keypair with secret seed [1; 32] in each encoding
+    const ADDRESS: &str = "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9";
+    const BASE58: &str = "2AXDGYSE4f2sz7tvMMzyHvUfcoJmxudvdhBcmiUSo6iuCXagjUCKEQF21awZnUGxmwD4m9vGXuC3qieHXJQHAcT";
+    const BASE64: &str = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQGKiOPddAnxlf1S2y08ul1yymcJvx2UEhvzdIgBtA9vXA==";
+    const HEX: &str = "0101010101010101010101010101010101010101010101010101010101010101\
+                       8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c";
+
+    fn json_array(hex: &str) -> String {
+        serde_json::to_string(&hex::decode(hex).unwrap()).unwrap()
+    }
+
+    fn assert_imports(input: &str, format: DetectedFormat, address: &str) {
+        let imported = import_secret(input).unwrap();
+        assert_eq!(imported.format, format, "{}", input);
+        assert_eq!(imported.keypair.pubkey().to_string(), address, "{}", input);
+    }
+
+    #[test]
+    fn imports_every_encoding() {
+        assert_imports(&json_array(HEX), DetectedFormat::JsonArray, ADDRESS);
+        assert_imports(BASE58, DetectedFormat::Base58, ADDRESS);
+        assert_imports(BASE64, DetectedFormat::Base64, ADDRESS);
+        assert_imports(HEX, DetectedFormat::Hex, ADDRESS);
+        assert_imports(&format!("0x{}", HEX), DetectedFormat::Hex, ADDRESS);
+        assert_imports(&format!("\n  {}\t\n", BASE58), DetectedFormat::Base58, ADDRESS);
+    }
+
+    #[test]
+    fn imports_seed_phrase_with_odd_spacing_and_capitals() {
+        // `solana-keygen recover` of the BIP39 test phrase, without a derivation path
+        let phrase = "  Abandon ABANDON\tabandon abandon  abandon abandon\n\
+                      abandon abandon abandon abandon abandon About ";
+        assert_imports(phrase, DetectedFormat::Mnemonic, "EHqmfkN89RJ7Y33CXM6uCzhVeuywHoJXZZLszBHHZy7o");
+    }
+
+    #[test]
+    fn input_fitting_several_formats_decodes_to_one_key() {
+        // A Phantom export is also valid base64 text, which decodes to the wrong length
+        assert_eq!(plausible_formats(BASE58), vec![DetectedFormat::Base58, DetectedFormat::Base64]);
+        assert_imports(BASE58, DetectedFormat::Base58, ADDRESS);
+
+        // Hex digits are base58 and base64 characters too
+        assert_eq!(
+            plausible_formats("abcd"),
+            vec![DetectedFormat::Hex, DetectedFormat::Base58, DetectedFormat::Base64]
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_embedded_pubkey() {
+        // The seed [1; 32] followed by the pubkey of seed [2; 32]
+        let mut bytes = keypair_from_seed(&[1; 32]).unwrap().to_bytes();
+        bytes[32..].copy_from_slice(&keypair_from_seed(&[2; 32]).unwrap().pubkey().to_bytes());
+
+        // A JSON array can only be one thing, so its own error comes back
+        let result = import_secret(&json_array(&hex::encode(bytes)));
+        assert!(matches!(result, Err(KeypairError::PubkeyMismatch { .. })), "{:?}", result.err());
+
+        // Base58 also fits base64, so both reasons are reported
+        match import_secret(&bs58::encode(bytes).into_string()) {
+            Err(KeypairError::UnrecognizedSecretFormat(reasons)) => {
+                assert!(reasons.contains("as base58"), "{}", reasons)
+            }
+            other => panic!("unexpected result {:?}", other.map(|imported| imported.keypair.pubkey())),
+        }
+    }
+
+    #[test]
+    fn rejects_decodes_that_disagree() {
+        // Real input cannot reach this, as two readings would each need a matching embedded
+        // pubkey, so the decoded candidates are built directly
+        let keypair = |seed| keypair_from_seed(&[seed; 32]).unwrap();
+        let decoded = vec![
+            ImportedKeypair { format: DetectedFormat::Hex, keypair: keypair(1) },
+            ImportedKeypair { format: DetectedFormat::Base58, keypair: keypair(2) },
+        ];
+        match agreed_keypair(decoded) {
+            Err(KeypairError::AmbiguousSecretFormat(formats)) => assert_eq!(formats, "hex and base58"),
+            other => panic!("unexpected result {:?}", other.map(|imported| imported.keypair.pubkey())),
+        }
+
+        let decoded = vec![
+            ImportedKeypair { format: DetectedFormat::Hex, keypair: keypair(1) },
+            ImportedKeypair { format: DetectedFormat::Base58, keypair: keypair(1) },
+        ];
+        assert_eq!(agreed_keypair(decoded).unwrap().format, DetectedFormat::Hex);
+    }
+
+    #[test]
+    fn rejects_unrecognized_input() {
+        for input in ["", "not a seed phrase 123", "{\"secretKey\": 1}"] {
+            let result = import_secret(input);
+            assert!(matches!(result, Err(KeypairError::UnrecognizedSecretFormat(_))), "{}", input);
+        }
+    }
+}
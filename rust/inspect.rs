@@ -0,0 +1,827 @@
+// Transaction inspector: decode a serialized transaction, legacy or v0, into its signers,
+// accounts and a readable description of every instruction, so it can be reviewed before
+// anyone signs it.
+//
+// System, SPL Token, Token-2022, associated token account, Memo, Compute Budget and address
+// lookup table instructions are decoded, as well as the token marketplace program, found at its
+// declared id unless another deployment is supplied.
+use crate::marketplace::MarketplaceInstruction;
+use crate::token::format_token_amount;
+use crate::transaction::{decode_versioned_transaction, TransactionEncoding};
+use crate::KeypairError;
+use borsh::BorshDeserialize;
+use serde::Serialize;
+use solana_sdk::address_lookup_table::instruction::ProgramInstruction as LookupTableInstruction;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::system_instruction::SystemInstruction;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::{address_lookup_table, compute_budget, system_program};
+use spl_token_2022::instruction::TokenInstruction;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectOptions {
+    // Where the marketplace program is deployed; its instructions stay undecoded without it
+    pub marketplace_program_id: Option<Pubkey>,
+    // Contents of address lookup tables, keyed by table address, used to resolve v0 accounts
+    pub lookup_tables: HashMap<Pubkey, Vec<Pubkey>>,
+}
+
+impl Default for InspectOptions {
+    fn default() -> Self {
+        InspectOptions {
+            marketplace_program_id: Some(solana_token_marketplace::id()),
+            lookup_tables: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectedSignature {
+    pub pubkey: String,
+    // None while the signer has not signed yet
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectedAccount {
+    // Base58 address, or `<table>[index]` for a lookup table entry that was not resolved
+    pub address: String,
+    pub signer: bool,
+    pub writable: bool,
+    pub fee_payer: bool,
+    // Lookup table the address was loaded from, for v0 transactions
+    pub lookup_table: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionAccount {
+    // Role of the account in the instruction, when known
+    pub name: Option<String>,
+    pub address: String,
+    pub signer: bool,
+    pub writable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectedInstruction {
+    pub program: String,
+    pub program_id: String,
+    // Instruction name, or "Unknown" when the data could not be decoded
+    pub name: String,
+    pub accounts: Vec<InstructionAccount>,
+    pub fields: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionInspection {
+    // "legacy" or "0"
+    pub version: String,
+    pub fee_payer: String,
+    // Recent blockhash, or the nonce value for durable nonce transactions
+    pub recent_blockhash: String,
+    pub signatures: Vec<InspectedSignature>,
+    pub accounts: Vec<InspectedAccount>,
+    pub instructions: Vec<InspectedInstruction>,
+}
+
+pub fn inspect_transaction(
+    encoded: &str,
+    encoding: TransactionEncoding,
+    options: &InspectOptions,
+) -> Result<TransactionInspection, KeypairError> {
+    inspect_versioned_transaction(&decode_versioned_transaction(encoded, encoding)?, options)
+}
+
+pub fn inspect_versioned_transaction(
+    transaction: &VersionedTransaction,
+    options: &InspectOptions,
+) -> Result<TransactionInspection, KeypairError> {
+    let message = &transaction.message;
+    let header = message.header();
+    let static_keys = message.static_account_keys();
+    let required_signatures = header.num_required_signatures as usize;
+    if static_keys.is_empty() || static_keys.len() < required_signatures {
+        return Err(KeypairError::InvalidTransaction(
+            "message lists fewer accounts than required signers".to_string(),
+        ));
+    }
+    if transaction.signatures.len() != required_signatures {
+        return Err(KeypairError::InvalidTransaction(format!(
+            "expected {} signatures, found {}",
+            required_signatures,
+            transaction.signatures.len()
+        )));
+    }
+
+    let writable_signed = required_signatures.saturating_sub(header.num_readonly_signed_accounts as usize);
+    let writable_unsigned = static_keys
+        .len()
+        .saturating_sub(header.num_readonly_unsigned_accounts as usize);
+    let mut accounts: Vec<InspectedAccount> = static_keys
+        .iter()
+        .enumerate()
+        .map(|(index, key)| {
+            let signer = index < required_signatures;
+            InspectedAccount {
+                address: key.to_string(),
+                signer,
+                writable: if signer { index < writable_signed } else { index < writable_unsigned },
+                fee_payer: index == 0,
+                lookup_table: None,
+            }
+        })
+        .collect();
+
+    // Loaded addresses follow the static keys: every table's writable entries, then every
+    // table's readonly entries
+    if let VersionedMessage::V0(message) = message {
+        for writable in [true, false] {
+            for lookup in &message.address_table_lookups {
+                let indexes = if writable { &lookup.writable_indexes } else { &lookup.readonly_indexes };
+                let table = options.lookup_tables.get(&lookup.account_key);
+                for index in indexes {
+                    let address = match table {
+                        Some(table) => table.get(*index as usize).map(|key| key.to_string()).ok_or_else(|| {
+                            KeypairError::InvalidTransaction(format!(
+                                "lookup table {} has no entry {}",
+                                lookup.account_key, index
+                            ))
+                        })?,
+                        None => format!("{}[{}]", lookup.account_key, index),
+                    };
+                    accounts.push(InspectedAccount {
+                        address,
+                        signer: false,
+                        writable,
+                        fee_payer: false,
+                        lookup_table: Some(lookup.account_key.to_string()),
+                    });
+                }
+            }
+        }
+    }
+
+    let signatures = static_keys
+        .iter()
+        .zip(&transaction.signatures)
+        .map(|(pubkey, signature)| InspectedSignature {
+            pubkey: pubkey.to_string(),
+            signature: (*signature != Signature::default()).then(|| signature.to_string()),
+        })
+        .collect();
+
+    let instructions = message
+        .instructions()
+        .iter()
+        .map(|instruction| {
+            let program_id = accounts
+                .get(instruction.program_id_index as usize)
+                .ok_or_else(|| {
+                    KeypairError::InvalidTransaction(format!(
+                        "program index {} is out of range",
+                        instruction.program_id_index
+                    ))
+                })?
+                .address
+                .clone();
+            let instruction_accounts = instruction
+                .accounts
+                .iter()
+                .map(|index| {
+                    accounts.get(*index as usize).cloned().ok_or_else(|| {
+                        KeypairError::InvalidTransaction(format!("account index {} is out of range", index))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(decode_instruction(&program_id, &instruction_accounts, &instruction.data, options))
+        })
+        .collect::<Result<Vec<_>, KeypairError>>()?;
+
+    Ok(TransactionInspection {
+        version: match message {
+            VersionedMessage::Legacy(_) => "legacy".to_string(),
+            VersionedMessage::V0(_) => "0".to_string(),
+        },
+        fee_payer: static_keys[0].to_string(),
+        recent_blockhash: message.recent_blockhash().to_string(),
+        signatures,
+        accounts,
+        instructions,
+    })
+}
+
+// Decode one instruction; data that no decoder recognizes is shown as hex under "Unknown"
+pub fn decode_instruction(
+    program_id: &str,
+    accounts: &[InspectedAccount],
+    data: &[u8],
+    options: &InspectOptions,
+) -> InspectedInstruction {
+    let program_key = program_id.parse::<Pubkey>().ok();
+    let decoded = match program_key {
+        Some(key) if key == system_program::id() => Some(("System", decode_system(data))),
+        Some(key) if key == spl_token::id() => Some(("SPL Token", decode_token(data))),
+        Some(key) if key == spl_token_2022::id() => Some(("Token-2022", decode_token(data))),
+        Some(key) if key == spl_associated_token_account::id() => {
+            Some(("Associated Token Account", decode_associated_token_account(data)))
+        }
+        Some(key) if key == spl_memo::id() || key == spl_memo::v1::id() => Some(("SPL Memo", decode_memo(data))),
+        Some(key) if key == compute_budget::id() => Some(("Compute Budget", decode_compute_budget(data))),
+        Some(key) if key == address_lookup_table::program::id() => {
+            Some(("Address Lookup Table", decode_lookup_table(data)))
+        }
+        Some(key) if Some(key) == options.marketplace_program_id => {
+            Some(("Token Marketplace", decode_marketplace(data)))
+        }
+        _ => None,
+    };
+
+    let (program, decoded) = match decoded {
+        Some((program, decoded)) => (program.to_string(), decoded),
+        None => ("Unknown program".to_string(), None),
+    };
+    let (name, account_names, fields) =
+        decoded.unwrap_or_else(|| ("Unknown", &[][..], vec![field("data", hex::encode(data))]));
+
+    InspectedInstruction {
+        program,
+        program_id: program_id.to_string(),
+        name: name.to_string(),
+        accounts: accounts
+            .iter()
+            .enumerate()
+            .map(|(index, account)| InstructionAccount {
+                name: account_names.get(index).map(|name| name.to_string()),
+                address: account.address.clone(),
+                signer: account.signer,
+                writable: account.writable,
+            })
+            .collect(),
+        fields,
+    }
+}
+
+// Instruction name, the roles of its accounts in order, and its decoded fields
+type Decoded = Option<(&'static str, &'static [&'static str], Vec<(String, String)>)>;
+
+fn field(name: &str, value: impl ToString) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+fn lamports(lamports: u64) -> String {
+    format!("{} ({} SOL)", lamports, lamports_to_sol(lamports))
+}
+
+fn decode_system(data: &[u8]) -> Decoded {
+    let instruction: SystemInstruction = bincode::deserialize(data).ok()?;
+    Some(match instruction {
+        SystemInstruction::CreateAccount { lamports: amount, space, owner } => (
+            "CreateAccount",
+            &["funder", "new account"],
+            vec![field("lamports", lamports(amount)), field("space", space), field("owner", owner)],
+        ),
+        SystemInstruction::Assign { owner } => ("Assign", &["account"], vec![field("owner", owner)]),
+        SystemInstruction::Transfer { lamports: amount } => {
+            ("Transfer", &["from", "to"], vec![field("lamports", lamports(amount))])
+        }
+        SystemInstruction::CreateAccountWithSeed { base, seed, lamports: amount, space, owner } => (
+            "CreateAccountWithSeed",
+            &["funder", "new account", "base"],
+            vec![
+                field("base", base),
+                field("seed", seed),
+                field("lamports", lamports(amount)),
+                field("space", space),
+                field("owner", owner),
+            ],
+        ),
+        SystemInstruction::AdvanceNonceAccount => (
+            "AdvanceNonceAccount",
+            &["nonce account", "recent blockhashes sysvar", "nonce authority"],
+            vec![],
+        ),
+        SystemInstruction::WithdrawNonceAccount(amount) => (
+            "WithdrawNonceAccount",
+            &["nonce account", "recipient", "recent blockhashes sysvar", "rent sysvar", "nonce authority"],
+            vec![field("lamports", lamports(amount))],
+        ),
+        SystemInstruction::InitializeNonceAccount(authority) => (
+            "InitializeNonceAccount",
+            &["nonce account", "recent blockhashes sysvar", "rent sysvar"],
+            vec![field("authority", authority)],
+        ),
+        SystemInstruction::AuthorizeNonceAccount(authority) => (
+            "AuthorizeNonceAccount",
+            &["nonce account", "nonce authority"],
+            vec![field("new authority", authority)],
+        ),
+        SystemInstruction::Allocate { space } => ("Allocate", &["account"], vec![field("space", space)]),
+        SystemInstruction::AllocateWithSeed { base, seed, space, owner } => (
+            "AllocateWithSeed",
+            &["account", "base"],
+            vec![field("base", base), field("seed", seed), field("space", space), field("owner", owner)],
+        ),
+        SystemInstruction::AssignWithSeed { base, seed, owner } => (
+            "AssignWithSeed",
+            &["account", "base"],
+            vec![field("base", base), field("seed", seed), field("owner", owner)],
+        ),
+        SystemInstruction::TransferWithSeed { lamports: amount, from_seed, from_owner } => (
+            "TransferWithSeed",
+            &["from", "from base", "to"],
+            vec![
+                field("lamports", lamports(amount)),
+                field("from seed", from_seed),
+                field("from owner", from_owner),
+            ],
+        ),
+        SystemInstruction::UpgradeNonceAccount => ("UpgradeNonceAccount", &["nonce account"], vec![]),
+    })
+}
+
+fn optional_key(key: Option<Pubkey>) -> String {
+    key.map(|key| key.to_string()).unwrap_or_else(|| "none".to_string())
+}
+
+// Token-2022 is a superset of the original token program's instruction layout, so one decoder
+// covers both. The deprecated unchecked instructions still show up in older transactions.
+#[allow(deprecated)]
+fn decode_token(data: &[u8]) -> Decoded {
+    let instruction = TokenInstruction::unpack(data).ok()?;
+    Some(match instruction {
+        TokenInstruction::InitializeMint { decimals, mint_authority, freeze_authority }
+        | TokenInstruction::InitializeMint2 { decimals, mint_authority, freeze_authority } => (
+            if data[0] == 0 { "InitializeMint" } else { "InitializeMint2" },
+            &["mint"],
+            vec![
+                field("decimals", decimals),
+                field("mint authority", mint_authority),
+                field("freeze authority", optional_key(freeze_authority.into())),
+            ],
+        ),
+        TokenInstruction::InitializeAccount => ("InitializeAccount", &["account", "mint", "owner"], vec![]),
+        TokenInstruction::InitializeAccount2 { owner } => {
+            ("InitializeAccount2", &["account", "mint"], vec![field("owner", owner)])
+        }
+        TokenInstruction::InitializeAccount3 { owner } => {
+            ("InitializeAccount3", &["account", "mint"], vec![field("owner", owner)])
+        }
+        TokenInstruction::Transfer { amount } => {
+            ("Transfer", &["source", "destination", "owner"], vec![field("amount", amount)])
+        }
+        TokenInstruction::TransferChecked { amount, decimals } => (
+            "TransferChecked",
+            &["source", "mint", "destination", "owner"],
+            vec![field("amount", ui_amount(amount, decimals)), field("decimals", decimals)],
+        ),
+        TokenInstruction::Approve { amount } => {
+            ("Approve", &["source", "delegate", "owner"], vec![field("amount", amount)])
+        }
+        TokenInstruction::ApproveChecked { amount, decimals } => (
+            "ApproveChecked",
+            &["source", "mint", "delegate", "owner"],
+            vec![field("amount", ui_amount(amount, decimals)), field("decimals", decimals)],
+        ),
+        TokenInstruction::Revoke => ("Revoke", &["source", "owner"], vec![]),
+        TokenInstruction::SetAuthority { authority_type, new_authority } => (
+            "SetAuthority",
+            &["account", "current authority"],
+            vec![
+                field("authority type", format!("{:?}", authority_type)),
+                field("new authority", optional_key(new_authority.into())),
+            ],
+        ),
+        TokenInstruction::MintTo { amount } => {
+            ("MintTo", &["mint", "destination", "mint authority"], vec![field("amount", amount)])
+        }
+        TokenInstruction::MintToChecked { amount, decimals } => (
+            "MintToChecked",
+            &["mint", "destination", "mint authority"],
+            vec![field("amount", ui_amount(amount, decimals)), field("decimals", decimals)],
+        ),
+        TokenInstruction::Burn { amount } => ("Burn", &["account", "mint", "owner"], vec![field("amount", amount)]),
+        TokenInstruction::BurnChecked { amount, decimals } => (
+            "BurnChecked",
+            &["account", "mint", "owner"],
+            vec![field("amount", ui_amount(amount, decimals)), field("decimals", decimals)],
+        ),
+        TokenInstruction::CloseAccount => ("CloseAccount", &["account", "destination", "owner"], vec![]),
+        TokenInstruction::FreezeAccount => ("FreezeAccount", &["account", "mint", "freeze authority"], vec![]),
+        TokenInstruction::ThawAccount => ("ThawAccount", &["account", "mint", "freeze authority"], vec![]),
+        TokenInstruction::SyncNative => ("SyncNative", &["account"], vec![]),
+        TokenInstruction::InitializeImmutableOwner => ("InitializeImmutableOwner", &["account"], vec![]),
+        TokenInstruction::InitializeMintCloseAuthority { close_authority } => (
+            "InitializeMintCloseAuthority",
+            &["mint"],
+            vec![field("close authority", optional_key(close_authority.into()))],
+        ),
+        TokenInstruction::InitializeMultisig { m } => {
+            ("InitializeMultisig", &["multisig"], vec![field("required signers", m)])
+        }
+        TokenInstruction::InitializeMultisig2 { m } => {
+            ("InitializeMultisig2", &["multisig"], vec![field("required signers", m)])
+        }
+        TokenInstruction::GetAccountDataSize { extension_types } => (
+            "GetAccountDataSize",
+            &["mint"],
+            vec![field("extensions", format!("{:?}", extension_types))],
+        ),
+        TokenInstruction::AmountToUiAmount { amount } => {
+            ("AmountToUiAmount", &["mint"], vec![field("amount", amount)])
+        }
+        TokenInstruction::UiAmountToAmount { ui_amount } => {
+            ("UiAmountToAmount", &["mint"], vec![field("ui amount", ui_amount)])
+        }
+        TokenInstruction::Reallocate { extension_types } => (
+            "Reallocate",
+            &["account", "payer", "system program", "owner"],
+            vec![field("extensions", format!("{:?}", extension_types))],
+        ),
+        TokenInstruction::CreateNativeMint => ("CreateNativeMint", &["payer", "native mint", "system program"], vec![]),
+        TokenInstruction::InitializeNonTransferableMint => ("InitializeNonTransferableMint", &["mint"], vec![]),
+        TokenInstruction::InitializePermanentDelegate { delegate } => {
+            ("InitializePermanentDelegate", &["mint"], vec![field("delegate", delegate)])
+        }
+        // Extension instructions carry their own sub-instruction, which is not decoded further
+        TokenInstruction::TransferFeeExtension(_) => ("TransferFeeExtension", &[], vec![]),
+        TokenInstruction::ConfidentialTransferExtension => ("ConfidentialTransferExtension", &[], vec![]),
+        TokenInstruction::DefaultAccountStateExtension => ("DefaultAccountStateExtension", &[], vec![]),
+        TokenInstruction::MemoTransferExtension => ("MemoTransferExtension", &[], vec![]),
+        TokenInstruction::InterestBearingMintExtension => ("InterestBearingMintExtension", &[], vec![]),
+        TokenInstruction::CpiGuardExtension => ("CpiGuardExtension", &[], vec![]),
+    })
+}
+
+// Raw amount alongside its decimal form, e.g. "1500000 (1.5)"
+fn ui_amount(amount: u64, decimals: u8) -> String {
+    format!("{} ({})", amount, format_token_amount(amount, decimals))
+}
+
+fn decode_associated_token_account(data: &[u8]) -> Decoded {
+    const ACCOUNTS: &[&str] = &[
+        "payer",
+        "associated token account",
+        "wallet",
+        "mint",
+        "system program",
+        "token program",
+    ];
+    // An empty instruction is the original Create
+    match data {
+        [] | [0] => Some(("Create", ACCOUNTS, vec![])),
+        [1] => Some(("CreateIdempotent", ACCOUNTS, vec![])),
+        [2] => Some((
+            "RecoverNested",
+            &[
+                "nested account",
+                "nested mint",
+                "destination account",
+                "owner account",
+                "owner mint",
+                "wallet",
+                "token program",
+            ],
+            vec![],
+        )),
+        _ => None,
+    }
+}
+
+fn decode_memo(data: &[u8]) -> Decoded {
+    let memo = std::str::from_utf8(data).ok()?;
+    Some(("Memo", &[], vec![field("memo", memo)]))
+}
+
+// Compute budget instructions are a tag byte followed by a little-endian integer
+fn decode_compute_budget(data: &[u8]) -> Decoded {
+    let (tag, rest) = data.split_first()?;
+    let u32_value = || rest.try_into().ok().map(u32::from_le_bytes);
+    Some(match tag {
+        1 => ("RequestHeapFrame", &[], vec![field("bytes", u32_value()?)]),
+        2 => ("SetComputeUnitLimit", &[], vec![field("units", u32_value()?)]),
+        3 => {
+            let price = u64::from_le_bytes(rest.try_into().ok()?);
+            ("SetComputeUnitPrice", &[], vec![field("micro-lamports per unit", price)])
+        }
+        4 => ("SetLoadedAccountsDataSizeLimit", &[], vec![field("bytes", u32_value()?)]),
+        _ => return None,
+    })
+}
+
+fn decode_lookup_table(data: &[u8]) -> Decoded {
+    let instruction: LookupTableInstruction = bincode::deserialize(data).ok()?;
+    Some(match instruction {
+        LookupTableInstruction::CreateLookupTable { recent_slot, bump_seed } => (
+            "CreateLookupTable",
+            &["lookup table", "authority", "payer", "system program"],
+            vec![field("recent slot", recent_slot), field("bump seed", bump_seed)],
+        ),
+        LookupTableInstruction::FreezeLookupTable => ("FreezeLookupTable", &["lookup table", "authority"], vec![]),
+        LookupTableInstruction::ExtendLookupTable { new_addresses } => (
+            "ExtendLookupTable",
+            &["lookup table", "authority", "payer", "system program"],
+            new_addresses
+                .iter()
+                .enumerate()
+                .map(|(index, address)| field(&format!("address {}", index), address))
+                .collect(),
+        ),
+        LookupTableInstruction::DeactivateLookupTable => {
+            ("DeactivateLookupTable", &["lookup table", "authority"], vec![])
+        }
+        LookupTableInstruction::CloseLookupTable => {
+            ("CloseLookupTable", &["lookup table", "authority", "recipient"], vec![])
+        }
+    })
+}
+
+fn decode_marketplace(data: &[u8]) -> Decoded {
+    let instruction = MarketplaceInstruction::try_from_slice(data).ok()?;
+    Some(match instruction {
+        MarketplaceInstruction::InitializeMarketplace { fee_percentage } => (
+            "InitializeMarketplace",
+            &["admin", "marketplace", "system program"],
+            vec![field("fee", format!("{} basis points", fee_percentage))],
+        ),
+        MarketplaceInstruction::CreateSellOrder { amount, price } => (
+            "CreateSellOrder",
+            &[
+                "seller",
+                "seller token account",
+                "order",
+                "order vault",
+                "marketplace",
+                "seller state",
+                "token mint",
+                "system program",
+                "token program",
+            ],
+            vec![field("amount", amount), field("price per token", lamports(price))],
+        ),
+        MarketplaceInstruction::BuyTokens { amount } => (
+            "BuyTokens",
+            &[
+                "buyer",
+                "buyer token account",
+                "seller",
+                "order vault",
+                "order",
+                "marketplace",
+                "admin",
+                "token mint",
+                "system program",
+                "token program",
+                "associated token program",
+            ],
+            vec![field("amount", amount)],
+        ),
+        MarketplaceInstruction::CancelOrder => (
+            "CancelOrder",
+            &["seller", "seller token account", "order", "order vault", "token program"],
+            vec![],
+        ),
+        MarketplaceInstruction::UpdatePrice { new_price } => (
+            "UpdatePrice",
+            &["seller", "order"],
+            vec![field("new price per token", lamports(new_price))],
+        ),
+        // Followed by an order, order vault and seller account per swept order
+        MarketplaceInstruction::SweepOrders => ("SweepOrders", &["admin", "marketplace", "token program"], vec![]),
+    })
+}
+
+fn access(signer: bool, writable: bool) -> &'static str {
+    match (signer, writable) {
+        (true, true) => "signer, writable",
+        (true, false) => "signer, readonly",
+        (false, true) => "writable",
+        (false, false) => "readonly",
+    }
+}
+
+impl fmt::Display for TransactionInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Fee payer: {}", self.fee_payer)?;
+        writeln!(f, "Recent blockhash: {}", self.recent_blockhash)?;
+
+        writeln!(f, "\nSigners:")?;
+        for signature in &self.signatures {
+            let status = signature.signature.as_deref().unwrap_or("not signed");
+            writeln!(f, "  {} ({})", signature.pubkey, status)?;
+        }
+
+        writeln!(f, "\nAccounts:")?;
+        for (index, account) in self.accounts.iter().enumerate() {
+            let mut notes = access(account.signer, account.writable).to_string();
+            if account.fee_payer {
+                notes.push_str(", fee payer");
+            }
+            if let Some(table) = &account.lookup_table {
+                notes.push_str(&format!(", from lookup table {}", table));
+            }
+            writeln!(f, "  {:>2}. {} ({})", index, account.address, notes)?;
+        }
+
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            writeln!(
+                f,
+                "\nInstruction {}: {} {} ({})",
+                index, instruction.program, instruction.name, instruction.program_id
+            )?;
+            for (name, value) in &instruction.fields {
+                writeln!(f, "    {}: {}", name, value)?;
+            }
+            for account in &instruction.accounts {
+                let name = account.name.as_deref().unwrap_or("account");
+                writeln!(
+                    f,
+                    "    {}: {} ({})",
+                    name,
+                    account.address,
+                    access(account.signer, account.writable)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::{create_associated_token_account_idempotent, TokenProgram};
+    use crate::transaction::{encode_versioned_transaction, memo_instruction, PriorityFee};
+    use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::{v0, Message};
+    use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::Transaction;
+
+    const ENCODINGS: [TransactionEncoding; 2] = [TransactionEncoding::Base64, TransactionEncoding::Base58];
+
+    fn key(seed: u8) -> Pubkey {
+        keypair_from_seed(&[seed; 32]).unwrap().pubkey()
+    }
+
+    fn payer() -> Keypair {
+        keypair_from_seed(&[1; 32]).unwrap()
+    }
+
+    // One instruction for each decoded program, all signed by the payer
+    fn instructions() -> Vec<Instruction> {
+        let payer = payer().pubkey();
+        let (recipient, mint, order) = (key(2), key(3), key(4));
+        let source = spl_associated_token_account::get_associated_token_address(&payer, &mint);
+        let destination = spl_associated_token_account::get_associated_token_address(&recipient, &mint);
+
+        let mut instructions = PriorityFee { compute_unit_limit: Some(200_000), compute_unit_price: Some(5_000) }
+            .instructions();
+        instructions.extend([
+            system_instruction::transfer(&payer, &recipient, 1_500_000_000),
+            create_associated_token_account_idempotent(&payer, &recipient, &mint, TokenProgram::Token),
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                &source,
+                &mint,
+                &destination,
+                &payer,
+                &[],
+                1_500_000,
+                6,
+            )
+            .unwrap(),
+            solana_token_marketplace::instruction::update_price(
+                &solana_token_marketplace::id(),
+                &payer,
+                &order,
+                2_500_000,
+            ),
+            memo_instruction("inspect me", &[&payer]),
+        ]);
+        instructions
+    }
+
+    // Program, instruction name and fields of each instruction
+    type Summary<'a> = Vec<(&'a str, &'a str, Vec<(&'a str, &'a str)>)>;
+
+    fn summary(inspection: &TransactionInspection) -> Summary<'_> {
+        inspection
+            .instructions
+            .iter()
+            .map(|instruction| {
+                let fields = instruction.fields.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+                (instruction.program.as_str(), instruction.name.as_str(), fields)
+            })
+            .collect()
+    }
+
+    fn assert_decoded(inspection: &TransactionInspection) {
+        assert_eq!(
+            summary(inspection),
+            vec![
+                ("Compute Budget", "SetComputeUnitLimit", vec![("units", "200000")]),
+                ("Compute Budget", "SetComputeUnitPrice", vec![("micro-lamports per unit", "5000")]),
+                ("System", "Transfer", vec![("lamports", "1500000000 (1.5 SOL)")]),
+                ("Associated Token Account", "CreateIdempotent", vec![]),
+                ("SPL Token", "TransferChecked", vec![("amount", "1500000 (1.5)"), ("decimals", "6")]),
+                ("Token Marketplace", "UpdatePrice", vec![("new price per token", "2500000 (0.0025 SOL)")]),
+                ("SPL Memo", "Memo", vec![("memo", "inspect me")]),
+            ]
+        );
+
+        let payer = payer().pubkey().to_string();
+        assert_eq!(inspection.fee_payer, payer);
+        assert_eq!(inspection.recent_blockhash, Hash::new_from_array([7; 32]).to_string());
+        assert_eq!(inspection.signatures.len(), 1);
+        assert!(inspection.signatures[0].signature.is_some());
+
+        let transfer = &inspection.instructions[2];
+        let roles: Vec<(Option<&str>, &str)> = transfer
+            .accounts
+            .iter()
+            .map(|account| (account.name.as_deref(), account.address.as_str()))
+            .collect();
+        assert_eq!(roles, vec![(Some("from"), payer.as_str()), (Some("to"), key(2).to_string().as_str())]);
+        assert!(transfer.accounts[0].signer && transfer.accounts[0].writable);
+    }
+
+    #[test]
+    fn inspects_legacy_transaction() {
+        let payer = payer();
+        let message = Message::new(&instructions(), Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, Hash::new_from_array([7; 32]));
+        let transaction = VersionedTransaction::from(transaction);
+
+        for encoding in ENCODINGS {
+            let encoded = encode_versioned_transaction(&transaction, encoding).unwrap();
+            let inspection = inspect_transaction(&encoded, encoding, &InspectOptions::default()).unwrap();
+            assert_eq!(inspection.version, "legacy");
+            assert_decoded(&inspection);
+            assert!(inspection.accounts.iter().all(|account| account.lookup_table.is_none()));
+        }
+    }
+
+    #[test]
+    fn inspects_v0_transaction_with_lookup_table() {
+        let payer = payer();
+        let table = AddressLookupTableAccount { key: key(9), addresses: vec![key(2), key(3), key(4)] };
+        let tables = [table.clone()];
+        let message =
+            v0::Message::try_compile(&payer.pubkey(), &instructions(), &tables, Hash::new_from_array([7; 32])).unwrap();
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        let mut options = InspectOptions::default();
+        options.lookup_tables.insert(table.key, table.addresses.clone());
+        for encoding in ENCODINGS {
+            let encoded = encode_versioned_transaction(&transaction, encoding).unwrap();
+            let inspection = inspect_transaction(&encoded, encoding, &options).unwrap();
+            assert_eq!(inspection.version, "0");
+            assert_decoded(&inspection);
+
+            // The recipient, mint and order are loaded from the table
+            let loaded: Vec<&str> = inspection
+                .accounts
+                .iter()
+                .filter(|account| account.lookup_table == Some(table.key.to_string()))
+                .map(|account| account.address.as_str())
+                .collect();
+            assert_eq!(loaded.len(), 3);
+            for address in &table.addresses {
+                assert!(loaded.contains(&address.to_string().as_str()));
+            }
+        }
+
+        // Without the table contents the loaded accounts are shown by table and index
+        let encoding = TransactionEncoding::Base64;
+        let encoded = encode_versioned_transaction(&transaction, encoding).unwrap();
+        let inspection = inspect_transaction(&encoded, encoding, &InspectOptions::default()).unwrap();
+        let prefix = format!("{}[", table.key);
+        assert!(inspection.instructions[2].accounts[1].address.starts_with(&prefix));
+    }
+
+    #[test]
+    fn marketplace_is_decoded_only_at_its_program_id() {
+        let payer = payer();
+        let message = Message::new(&instructions(), Some(&payer.pubkey()));
+        let transaction = VersionedTransaction::from(Transaction::new(&[&payer], message, Hash::default()));
+        let encoded = encode_versioned_transaction(&transaction, TransactionEncoding::Base64).unwrap();
+
+        let options = InspectOptions { marketplace_program_id: Some(key(5)), ..Default::default() };
+        let inspection = inspect_transaction(&encoded, TransactionEncoding::Base64, &options).unwrap();
+        assert_eq!(inspection.instructions[5].program, "Unknown program");
+        assert_eq!(inspection.instructions[5].name, "Unknown");
+    }
+}
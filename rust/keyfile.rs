@@ -0,0 +1,220 @@
+// Keypair files in the format used by `solana-keygen` and the `solana` CLI (~/.config/solana/id.json):
+// a JSON array of the 64 secret key bytes, e.g. `[12,250,...]`
+use crate::{keypair_from_base58, keypair_from_base64, keypair_from_bytes, keypair_from_hex, KeypairError};
+use base64::encode;
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+// Text encodings a secret key can be converted between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretKeyFormat {
+    // CLI keypair file contents
+    JsonArray,
+    // Phantom / web3.js export
+    Base58,
+    // `generate_keypair_json` secretKey
+    Base64,
+    // Raw 128-character hex dump
+    Hex,
+}
+
+impl std::fmt::Display for SecretKeyFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            SecretKeyFormat::JsonArray => "JSON byte array",
+            SecretKeyFormat::Base58 => "base58",
+            SecretKeyFormat::Base64 => "base64",
+            SecretKeyFormat::Hex => "hex",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn io_error(path: &Path, e: io::Error) -> KeypairError {
+    KeypairError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    }
+}
+
+// Serialize a keypair the same way `solana-keygen new` does
+pub fn keypair_to_json_array(keypair: &Keypair) -> String {
+    let bytes = keypair.to_bytes();
+    let numbers: Vec<String> = bytes.iter().map(|b| b.to_string()).collect();
+    format!("[{}]", numbers.join(","))
+}
+
+// Parse the contents of a CLI keypair file
+pub fn keypair_from_json_array(json: &str) -> Result<Keypair, KeypairError> {
+    let bytes: Vec<u8> = serde_json::from_str(json.trim()).map_err(|e| KeypairError::InvalidJson(e.to_string()))?;
+    keypair_from_bytes(&bytes)
+}
+
+// Read a keypair file such as ~/.config/solana/id.json
+pub fn read_keypair_file<P: AsRef<Path>>(path: P) -> Result<Keypair, KeypairError> {
+    let contents = fs::read_to_string(path.as_ref()).map_err(|e| io_error(path.as_ref(), e))?;
+    keypair_from_json_array(&contents)
+}
+
+// Write a keypair file readable by the Solana CLI and return its pubkey.
+// The file is created with 0600 permissions on Unix, and an existing file is
+// only replaced when `overwrite` is set.
+pub fn write_keypair_file<P: AsRef<Path>>(
+    keypair: &Keypair,
+    path: P,
+    overwrite: bool,
+) -> Result<String, KeypairError> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+        }
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        // create_new fails atomically if the file is already there
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(path).map_err(|e| {
+        if e.kind() == io::ErrorKind::AlreadyExists {
+            KeypairError::KeypairFileExists(path.display().to_string())
+        } else {
+            io_error(path, e)
+        }
+    })?;
+
+    // mode() only applies to new files, so tighten an overwritten one explicitly
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600)).map_err(|e| io_error(path, e))?;
+
+    file.write_all(keypair_to_json_array(keypair).as_bytes()).map_err(|e| io_error(path, e))?;
+    file.sync_all().map_err(|e| io_error(path, e))?;
+
+    Ok(keypair.pubkey().to_string())
+}
+
+// Decode a secret key from one of the supported encodings
+pub fn keypair_from_format(input: &str, format: SecretKeyFormat) -> Result<Keypair, KeypairError> {
+    match format {
+        SecretKeyFormat::JsonArray => keypair_from_json_array(input),
+        SecretKeyFormat::Base58 => keypair_from_base58(input.trim()),
+        SecretKeyFormat::Base64 => keypair_from_base64(input.trim()),
+        SecretKeyFormat::Hex => keypair_from_hex(input.trim()),
+    }
+}
+
+// Encode a keypair's secret key in one of the supported encodings
+pub fn keypair_to_format(keypair: &Keypair, format: SecretKeyFormat) -> String {
+    match format {
+        SecretKeyFormat::JsonArray => keypair_to_json_array(keypair),
+        SecretKeyFormat::Base58 => keypair.to_base58_string(),
+        SecretKeyFormat::Base64 => encode(keypair.to_bytes()),
+        SecretKeyFormat::Hex => hex::encode(keypair.to_bytes()),
+    }
+}
+
+// Convert a secret key between encodings, e.g. an id.json array into a Phantom base58 string
+pub fn convert_secret_key(
+    input: &str,
+    from: SecretKeyFormat,
+    to: SecretKeyFormat,
+) -> Result<String, KeypairError> {
+    let keypair = keypair_from_format(input, from)?;
+    Ok(keypair_to_format(&keypair, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::keypair_from_seed;
+
+    const SECRET_BASE58: &str =
+        "2AXDGYSE4f2sz7tvMMzyHvUfcoJmxudvdhBcmiUSo6iuCXagjUCKEQF21awZnUGxmwD4m9vGXuC3qieHXJQHAcT";
+
+    fn keypair(seed: u8) -> Keypair {
+        keypair_from_seed(&[seed; 32]).unwrap()
+    }
+
+    #[test]
+    fn converts_between_formats() {
+        let keypair = keypair(1);
+        let json = keypair_to_json_array(&keypair);
+        assert!(json.starts_with("[1,1,1,"));
+        assert!(json.ends_with(",15,111,92]"));
+        assert_eq!(
+            convert_secret_key(&json, SecretKeyFormat::JsonArray, SecretKeyFormat::Base58).unwrap(),
+            SECRET_BASE58
+        );
+        use SecretKeyFormat::*;
+        for format in [JsonArray, Base58, Base64, Hex] {
+            let encoded = format!(" {}\n", keypair_to_format(&keypair, format));
+            assert_eq!(keypair_from_format(&encoded, format).unwrap(), keypair, "{}", format);
+        }
+        assert!(matches!(keypair_from_json_array("[1,2,"), Err(KeypairError::InvalidJson(_))));
+        assert!(matches!(keypair_from_json_array("[256]"), Err(KeypairError::InvalidJson(_))));
+        assert!(matches!(keypair_from_json_array("[1,2,3]"), Err(KeypairError::InvalidLength { actual: 3, .. })));
+    }
+
+    #[cfg(unix)]
+    mod files {
+        use super::*;
+        use std::os::unix::fs::PermissionsExt;
+
+        fn mode(path: &Path) -> u32 {
+            fs::metadata(path).unwrap().permissions().mode() & 0o777
+        }
+
+        #[test]
+        fn round_trips_through_a_file() {
+            let dir = tempfile::tempdir().unwrap();
+            // Missing parent directories are created, as for ~/.config/solana
+            let path = dir.path().join("config/solana/id.json");
+            let pubkey = write_keypair_file(&keypair(1), &path, false).unwrap();
+            assert_eq!(pubkey, "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9");
+            assert_eq!(fs::read_to_string(&path).unwrap(), keypair_to_json_array(&keypair(1)));
+            assert_eq!(read_keypair_file(&path).unwrap(), keypair(1));
+            assert_eq!(mode(&path), 0o600);
+        }
+
+        #[test]
+        fn refuses_to_overwrite_unless_asked() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("id.json");
+            write_keypair_file(&keypair(1), &path, false).unwrap();
+            assert_eq!(
+                write_keypair_file(&keypair(2), &path, false),
+                Err(KeypairError::KeypairFileExists(path.display().to_string()))
+            );
+            assert_eq!(read_keypair_file(&path).unwrap(), keypair(1));
+
+            // An overwritten file is tightened to 0600 even if it was readable before
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            let pubkey = write_keypair_file(&keypair(2), &path, true).unwrap();
+            assert_eq!(pubkey, "9hSR6S7WPtxmTojgo6GG3k4yDPecgJY292j7xrsUGWBu");
+            assert_eq!(read_keypair_file(&path).unwrap(), keypair(2));
+            assert_eq!(mode(&path), 0o600);
+        }
+
+        #[test]
+        fn reports_io_errors_with_the_path() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("missing.json");
+            match read_keypair_file(&path) {
+                Err(KeypairError::Io { path: reported, .. }) => assert_eq!(reported, path.display().to_string()),
+                other => panic!("expected an I/O error, got {:?}", other.map(|keypair| keypair.pubkey())),
+            }
+        }
+    }
+}
@@ -1,64 +1,138 @@
-use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
 use solana_sdk::pubkey::Pubkey;
 use base64::{encode, decode};
-use rand::rngs::OsRng;
-use std::error::Error;
+use sha2::{Digest, Sha256};
 use std::convert::TryFrom;
 
+pub mod account;
+pub mod derivation;
+pub mod error;
+pub mod export;
+pub mod grind;
+pub mod import;
+pub mod inspect;
+pub mod keyfile;
+pub mod keystore;
+pub mod lookup_table;
+pub mod marketplace;
+pub mod mnemonic;
+pub mod nonce;
+pub mod pda;
+pub mod shamir;
+pub mod signing;
+pub mod siws;
+pub mod token;
+pub mod transaction;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+pub use error::KeypairError;
+
 // Generate a new random Solana keypair
 pub fn generate_keypair() -> Keypair {
-    Keypair::generate(&mut OsRng)
+    // Keypair::new draws from OsRng internally
+    Keypair::new()
 }
 
 // Generate keypair as a JSON string
 pub fn generate_keypair_json() -> String {
     let keypair = generate_keypair();
-    let secret_key_bytes = keypair.to_bytes();
-    let pubkey_string = keypair.pubkey().to_string();
-    let secret_key_base64 = encode(&secret_key_bytes);
-    
-    format!(r#"{{
-  "pubkey": "{}",
-  "secretKey": "{}"
-}}"#, pubkey_string, secret_key_base64)
+    let json = serde_json::json!({
+        "pubkey": keypair.pubkey().to_string(),
+        "secretKey": encode(keypair.to_bytes()),
+    });
+    serde_json::to_string_pretty(&json).expect("keypair JSON serializes")
+}
+
+// Build a keypair from its 64 secret key bytes, checking that the embedded pubkey
+// really belongs to the secret half
+pub fn keypair_from_bytes(bytes: &[u8]) -> Result<Keypair, KeypairError> {
+    if bytes.len() != 64 {
+        return Err(KeypairError::InvalidLength { expected: "64", actual: bytes.len() });
+    }
+
+    let keypair = keypair_from_seed(&bytes[..32]).map_err(|e| KeypairError::InvalidSecretKey(e.to_string()))?;
+    let embedded = Pubkey::try_from(&bytes[32..]).map_err(|e| KeypairError::InvalidPubkey(e.to_string()))?;
+    if keypair.pubkey() != embedded {
+        return Err(KeypairError::PubkeyMismatch {
+            embedded: embedded.to_string(),
+            derived: keypair.pubkey().to_string(),
+        });
+    }
+    Ok(keypair)
 }
 
 // Generate a keypair from a base58 seed phrase
-pub fn keypair_from_base58(base58_string: &str) -> Result<Keypair, Box<dyn Error>> {
+pub fn keypair_from_base58(base58_string: &str) -> Result<Keypair, KeypairError> {
     let bytes = bs58::decode(base58_string).into_vec()?;
-    let keypair = Keypair::from_bytes(&bytes)?;
-    Ok(keypair)
+    keypair_from_bytes(&bytes)
 }
 
 // Generate a keypair from a base64 string
-pub fn keypair_from_base64(base64_string: &str) -> Result<Keypair, Box<dyn Error>> {
+pub fn keypair_from_base64(base64_string: &str) -> Result<Keypair, KeypairError> {
     let bytes = decode(base64_string)?;
-    let keypair = Keypair::from_bytes(&bytes)?;
-    Ok(keypair)
+    keypair_from_bytes(&bytes)
+}
+
+// Generate a keypair from a hex string, with or without a 0x prefix
+pub fn keypair_from_hex(hex_string: &str) -> Result<Keypair, KeypairError> {
+    let digits = hex_string.strip_prefix("0x").unwrap_or(hex_string);
+    let bytes = hex::decode(digits)?;
+    keypair_from_bytes(&bytes)
 }
 
-// Recover public key from private key
-pub fn pubkey_from_bytes(bytes: &[u8]) -> Result<Pubkey, Box<dyn Error>> {
-    if bytes.len() != 64 && bytes.len() != 32 {
-        return Err("Invalid key length".into());
+// Parse a base58 pubkey string
+pub fn parse_pubkey(address: &str) -> Result<Pubkey, KeypairError> {
+    let bytes = bs58::decode(address).into_vec()?;
+    if bytes.len() != 32 {
+        return Err(KeypairError::InvalidLength { expected: "32", actual: bytes.len() });
     }
-    
-    if bytes.len() == 64 {
-        // This is a full keypair, extract just the public key
-        let keypair = Keypair::from_bytes(bytes)?;
-        Ok(keypair.pubkey())
-    } else {
-        // This is just a public key
-        Ok(Pubkey::try_from(bytes)?)
+    Pubkey::try_from(bytes.as_slice()).map_err(|e| KeypairError::InvalidPubkey(e.to_string()))
+}
+
+// What a 32-byte input holds. The two cannot be told apart from the bytes alone: about half of
+// all random secret seeds also happen to be valid curve points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortKey {
+    Pubkey,
+    // The 32-byte ed25519 secret seed, i.e. the first half of a 64-byte secret key
+    SecretSeed,
+}
+
+// Recover the public key from a 64-byte secret key, or from 32 bytes read as `short_key`
+pub fn pubkey_from_bytes(bytes: &[u8], short_key: ShortKey) -> Result<Pubkey, KeypairError> {
+    match (bytes.len(), short_key) {
+        // A full keypair; the embedded pubkey is checked against the secret half
+        (64, _) => Ok(keypair_from_bytes(bytes)?.pubkey()),
+        (32, ShortKey::SecretSeed) => Ok(keypair_from_secret_seed(bytes)?.pubkey()),
+        (32, ShortKey::Pubkey) => Pubkey::try_from(bytes).map_err(|e| KeypairError::InvalidPubkey(e.to_string())),
+        (actual, _) => Err(KeypairError::InvalidLength { expected: "32 or 64", actual }),
     }
 }
 
-// Get the associated token account address for a given wallet and token mint
-pub fn get_associated_token_address(wallet_address: &str, token_mint: &str) -> Result<String, Box<dyn Error>> {
-    let wallet_pubkey = Pubkey::try_from(bs58::decode(wallet_address).into_vec()?.as_slice())?;
-    let token_mint_pubkey = Pubkey::try_from(bs58::decode(token_mint).into_vec()?.as_slice())?;
+// Expand a 32-byte ed25519 secret seed into its keypair
+pub fn keypair_from_secret_seed(seed: &[u8]) -> Result<Keypair, KeypairError> {
+    // keypair_from_seed would silently ignore anything past 32 bytes
+    if seed.len() != 32 {
+        return Err(KeypairError::InvalidLength { expected: "32", actual: seed.len() });
+    }
+    keypair_from_seed(seed).map_err(|e| KeypairError::InvalidSecretKey(e.to_string()))
+}
+
+// A keypair that is the same on every run for the same label, for tests and demo scripts.
+// Anyone can recompute it from the label, so never send real funds to it.
+pub fn fixture_keypair(label: &str) -> Keypair {
+    let seed = Sha256::digest(format!("solana-keypair-generator fixture:{}", label).as_bytes());
+    keypair_from_seed(&seed).expect("a sha256 digest is a valid 32-byte seed")
+}
+
+// Get the associated token account address for a given wallet and token mint.
+// This assumes the original SPL Token program; see `token` for Token-2022 mints.
+pub fn get_associated_token_address(wallet_address: &str, token_mint: &str) -> Result<String, KeypairError> {
+    let wallet_pubkey = parse_pubkey(wallet_address)?;
+    let token_mint_pubkey = parse_pubkey(token_mint)?;
     
-    let (associated_token_address, _) = spl_associated_token_account::get_associated_token_address_with_program_id(
+    let associated_token_address = spl_associated_token_account::get_associated_token_address_with_program_id(
         &wallet_pubkey,
         &token_mint_pubkey,
         &spl_token::id(),
@@ -66,3 +140,66 @@ pub fn get_associated_token_address(wallet_address: &str, token_mint: &str) -> R
     
     Ok(associated_token_address.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Computed independently: ed25519 public keys of the seeds below
+    const SEED_1_PUBKEY: &str = "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9";
+    const SEED_7_PUBKEY: &str = "GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB";
+
+    #[test]
+    fn fixture_keypairs_are_fixed_per_label() {
+        let alice = fixture_keypair("alice");
+        assert_eq!(alice.pubkey().to_string(), "AyiEneuYD41RV3h1kyjfyzT416yd1GXWvvk1zGzEH4jn");
+        assert_eq!(
+            alice.to_base58_string(),
+            "3w2h2a5G6x6Tovxuy2NiDcv8BP8itVEHPxm713DDxVHtAG6fDVzQ6qxZTMvcZiMKM6oS5TAd3jUspPoY8TFQRziG"
+        );
+        assert_eq!(fixture_keypair("bob").pubkey().to_string(), "6r9t86P3JynAExrLkQz3ZqzJbNmMQBvGBk8tvUTVkTX2");
+        assert_eq!(fixture_keypair("").pubkey().to_string(), "Efn2hJLJmRHquPZshfonLktPetJsKcPiWhim1yu7igYk");
+    }
+
+    #[test]
+    fn expands_secret_seeds() {
+        assert_eq!(keypair_from_secret_seed(&[1; 32]).unwrap().pubkey().to_string(), SEED_1_PUBKEY);
+        assert_eq!(keypair_from_secret_seed(&[7; 32]).unwrap().pubkey().to_string(), SEED_7_PUBKEY);
+        for length in [0, 31, 33, 64] {
+            assert_eq!(
+                keypair_from_secret_seed(&vec![1; length]).map(|keypair| keypair.pubkey()),
+                Err(KeypairError::InvalidLength { expected: "32", actual: length })
+            );
+        }
+    }
+
+    #[test]
+    fn reads_32_bytes_as_the_requested_kind() {
+        // The same bytes give different pubkeys depending on how they are read
+        let bytes = [7; 32];
+        assert_eq!(pubkey_from_bytes(&bytes, ShortKey::SecretSeed).unwrap().to_string(), SEED_7_PUBKEY);
+        assert_eq!(pubkey_from_bytes(&bytes, ShortKey::Pubkey).unwrap(), Pubkey::new_from_array(bytes));
+        assert_eq!(
+            pubkey_from_bytes(&[3; 32], ShortKey::Pubkey).unwrap().to_string(),
+            "CktRuQ2mttgRGkXJtyksdKHjUdc2C4TgDzyB98oEzy8"
+        );
+    }
+
+    #[test]
+    fn reads_64_bytes_as_a_checked_keypair() {
+        let keypair = fixture_keypair("alice");
+        for short_key in [ShortKey::Pubkey, ShortKey::SecretSeed] {
+            assert_eq!(pubkey_from_bytes(&keypair.to_bytes(), short_key).unwrap(), keypair.pubkey());
+        }
+        let mut mismatched = keypair.to_bytes();
+        mismatched[32..].copy_from_slice(&[3; 32]);
+        assert!(matches!(
+            pubkey_from_bytes(&mismatched, ShortKey::SecretSeed),
+            Err(KeypairError::PubkeyMismatch { .. })
+        ));
+        assert_eq!(
+            pubkey_from_bytes(&[1; 48], ShortKey::Pubkey),
+            Err(KeypairError::InvalidLength { expected: "32 or 64", actual: 48 })
+        );
+    }
+}
@@ -0,0 +1,323 @@
+// Password-encrypted keystore for exported secret keys.
+//
+// The keystore is a JSON document: the header (version, pubkey, KDF and cipher parameters) is
+// stored in the clear and bound to the ciphertext as associated data, so editing any of it makes
+// decryption fail. The secret key is sealed with XChaCha20-Poly1305 under a key stretched from the
+// password with scrypt.
+use base64::{decode, encode};
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, Signer};
+use thiserror::Error;
+
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const KDF_NAME: &str = "scrypt";
+const CIPHER_NAME: &str = "xchacha20poly1305";
+const SALT_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const KEY_LENGTH: usize = 32;
+
+// Upper bounds on the scrypt cost accepted when decrypting, so a crafted file cannot make the
+// browser allocate gigabytes. scrypt allocates 128 * r * N bytes and its work grows with
+// 128 * r * N * p, which is held to what N = 2^20, r = 8, p = 1 costs (1 GiB).
+const MAX_LOG_N: u8 = 20;
+const MAX_SCRYPT_COST: u128 = 128 * 8 * (1 << 20);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeystoreError {
+    // The password did not decrypt the keystore (or the file was tampered with)
+    #[error("Wrong password or corrupted keystore")]
+    WrongPassword,
+    #[error("Unsupported keystore version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Unsupported keystore algorithm '{0}'")]
+    UnsupportedAlgorithm(String),
+    #[error("Invalid keystore parameters: {0}")]
+    InvalidParameters(String),
+    #[error("Malformed keystore: {0}")]
+    Malformed(String),
+    // The decrypted secret does not belong to the pubkey in the header
+    #[error("Keystore secret does not match its pubkey")]
+    PubkeyMismatch,
+}
+
+// scrypt cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreParams {
+    #[serde(rename = "logN")]
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KeystoreParams {
+    // N = 2^15, r = 8, p = 1: about 32 MiB and well under a second in a browser
+    fn default() -> Self {
+        KeystoreParams { log_n: 15, r: 8, p: 1 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfHeader {
+    pub name: String,
+    #[serde(flatten)]
+    pub params: KeystoreParams,
+    pub salt: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherHeader {
+    pub name: String,
+    pub nonce: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    // Left unencrypted so a keystore can be matched to an address without the password
+    pub pubkey: String,
+    pub kdf: KdfHeader,
+    pub cipher: CipherHeader,
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("keystore serializes")
+    }
+
+    pub fn from_json(json: &str) -> Result<Keystore, KeystoreError> {
+        serde_json::from_str(json).map_err(|e| KeystoreError::Malformed(e.to_string()))
+    }
+
+    // Everything except the ciphertext, authenticated alongside it
+    fn associated_data(&self) -> Vec<u8> {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            self.version,
+            self.pubkey,
+            self.kdf.name,
+            self.kdf.params.log_n,
+            self.kdf.params.r,
+            self.kdf.params.p,
+            self.kdf.salt,
+            self.cipher.name,
+            self.cipher.nonce
+        )
+        .into_bytes()
+    }
+}
+
+// Reject parameters beyond the cost limits before any memory is allocated for them
+fn check_cost(params: &KeystoreParams) -> Result<(), KeystoreError> {
+    if params.log_n > MAX_LOG_N {
+        return Err(KeystoreError::InvalidParameters(format!(
+            "logN {} exceeds the maximum of {}",
+            params.log_n, MAX_LOG_N
+        )));
+    }
+    let cost = 128 * params.r as u128 * (1u128 << params.log_n) * params.p as u128;
+    if cost > MAX_SCRYPT_COST {
+        return Err(KeystoreError::InvalidParameters(format!(
+            "128 * r * N * p = {} exceeds the maximum of {}",
+            cost, MAX_SCRYPT_COST
+        )));
+    }
+    Ok(())
+}
+
+fn derive_key(password: &str, salt: &[u8], params: &KeystoreParams) -> Result<Key, KeystoreError> {
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, KEY_LENGTH)
+        .map_err(|e| KeystoreError::InvalidParameters(e.to_string()))?;
+    let mut key = [0u8; KEY_LENGTH];
+    scrypt::scrypt(password.as_bytes(), salt, &scrypt_params, &mut key)
+        .map_err(|e| KeystoreError::InvalidParameters(e.to_string()))?;
+    Ok(Key::from(key))
+}
+
+fn decode_field(name: &str, value: &str, expected_length: Option<usize>) -> Result<Vec<u8>, KeystoreError> {
+    let bytes = decode(value).map_err(|e| KeystoreError::Malformed(format!("{}: {}", name, e)))?;
+    match expected_length {
+        Some(length) if bytes.len() != length => Err(KeystoreError::Malformed(format!(
+            "{} must be {} bytes, found {}",
+            name,
+            length,
+            bytes.len()
+        ))),
+        _ => Ok(bytes),
+    }
+}
+
+// Encrypt a keypair with the default scrypt cost
+pub fn encrypt_keypair(keypair: &Keypair, password: &str) -> Result<Keystore, KeystoreError> {
+    encrypt_keypair_with_params(keypair, password, KeystoreParams::default())
+}
+
+pub fn encrypt_keypair_with_params(
+    keypair: &Keypair,
+    password: &str,
+    params: KeystoreParams,
+) -> Result<Keystore, KeystoreError> {
+    let mut salt = [0u8; SALT_LENGTH];
+    let mut nonce = [0u8; NONCE_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut keystore = Keystore {
+        version: KEYSTORE_VERSION,
+        pubkey: keypair.pubkey().to_string(),
+        kdf: KdfHeader {
+            name: KDF_NAME.to_string(),
+            params,
+            salt: encode(salt),
+        },
+        cipher: CipherHeader {
+            name: CIPHER_NAME.to_string(),
+            nonce: encode(nonce),
+        },
+        ciphertext: String::new(),
+    };
+
+    let key = derive_key(password, &salt, &params)?;
+    let cipher = XChaCha20Poly1305::new(&key);
+    let secret = keypair.to_bytes();
+    let ciphertext = cipher
+        .encrypt(
+            &XNonce::from(nonce),
+            Payload {
+                msg: &secret,
+                aad: &keystore.associated_data(),
+            },
+        )
+        .map_err(|_| KeystoreError::InvalidParameters("encryption failed".to_string()))?;
+
+    keystore.ciphertext = encode(ciphertext);
+    Ok(keystore)
+}
+
+// Decrypt a keystore, returning `KeystoreError::WrongPassword` when the password is wrong
+pub fn decrypt_keypair(keystore: &Keystore, password: &str) -> Result<Keypair, KeystoreError> {
+    if keystore.version != KEYSTORE_VERSION {
+        return Err(KeystoreError::UnsupportedVersion(keystore.version));
+    }
+    if keystore.kdf.name != KDF_NAME {
+        return Err(KeystoreError::UnsupportedAlgorithm(keystore.kdf.name.clone()));
+    }
+    if keystore.cipher.name != CIPHER_NAME {
+        return Err(KeystoreError::UnsupportedAlgorithm(keystore.cipher.name.clone()));
+    }
+    check_cost(&keystore.kdf.params)?;
+
+    let salt = decode_field("salt", &keystore.kdf.salt, None)?;
+    let nonce: [u8; NONCE_LENGTH] = decode_field("nonce", &keystore.cipher.nonce, Some(NONCE_LENGTH))?
+        .try_into()
+        .map_err(|_| KeystoreError::Malformed("nonce".to_string()))?;
+    let ciphertext = decode_field("ciphertext", &keystore.ciphertext, None)?;
+
+    let key = derive_key(password, &salt, &keystore.kdf.params)?;
+    let cipher = XChaCha20Poly1305::new(&key);
+    let secret = cipher
+        .decrypt(
+            &XNonce::from(nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: &keystore.associated_data(),
+            },
+        )
+        .map_err(|_| KeystoreError::WrongPassword)?;
+
+    let keypair = crate::keypair_from_bytes(&secret).map_err(|e| KeystoreError::Malformed(e.to_string()))?;
+    if keypair.pubkey().to_string() != keystore.pubkey {
+        return Err(KeystoreError::PubkeyMismatch);
+    }
+    Ok(keypair)
+}
+
+// Encrypt straight to the JSON text offered as a download
+pub fn encrypt_keypair_json(keypair: &Keypair, password: &str) -> Result<String, KeystoreError> {
+    Ok(encrypt_keypair(keypair, password)?.to_json())
+}
+
+pub fn decrypt_keypair_json(json: &str, password: &str) -> Result<Keypair, KeystoreError> {
+    decrypt_keypair(&Keystore::from_json(json)?, password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap enough to run unoptimized
+    const TEST_PARAMS: KeystoreParams = KeystoreParams { log_n: 4, r: 8, p: 1 };
+
+    fn keystore_with_params(params: KeystoreParams) -> (Keypair, Keystore) {
+        let keypair = Keypair::new();
+        let mut keystore = encrypt_keypair_with_params(&keypair, "password", TEST_PARAMS).unwrap();
+        keystore.kdf.params = params;
+        (keypair, keystore)
+    }
+
+    #[test]
+    fn round_trips_with_the_right_password() {
+        let (keypair, keystore) = keystore_with_params(TEST_PARAMS);
+        let json = keystore.to_json();
+        assert_eq!(decrypt_keypair_json(&json, "password").unwrap().to_bytes(), keypair.to_bytes());
+        assert_eq!(decrypt_keypair_json(&json, "wrong").unwrap_err(), KeystoreError::WrongPassword);
+    }
+
+    // Replace the sealed secret of a keystore with arbitrary bytes
+    fn reseal(keystore: &mut Keystore, secret: &[u8]) {
+        let salt = decode(&keystore.kdf.salt).unwrap();
+        let nonce: [u8; NONCE_LENGTH] = decode(&keystore.cipher.nonce).unwrap().try_into().unwrap();
+        let key = derive_key("password", &salt, &keystore.kdf.params).unwrap();
+        let payload = Payload { msg: secret, aad: &keystore.associated_data() };
+        let ciphertext = XChaCha20Poly1305::new(&key).encrypt(&XNonce::from(nonce), payload).unwrap();
+        keystore.ciphertext = encode(ciphertext);
+    }
+
+    #[test]
+    fn rejects_secret_with_foreign_pubkey() {
+        // One key's secret half followed by the pubkey named in the header
+        let (keypair, mut keystore) = keystore_with_params(TEST_PARAMS);
+        let mut secret = Keypair::new().to_bytes();
+        secret[32..].copy_from_slice(&keypair.pubkey().to_bytes());
+        reseal(&mut keystore, &secret);
+
+        match decrypt_keypair(&keystore, "password") {
+            Err(KeystoreError::Malformed(_)) => {}
+            other => panic!("mismatched secret accepted: {:?}", other.map(|keypair| keypair.pubkey())),
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_cost_before_deriving() {
+        // Just past each limit, and far past it: r = 2^20 with N = 2^20 would need 128 TiB, so
+        // reaching the KDF would abort the test rather than return an error
+        let oversized = [
+            KeystoreParams { log_n: 21, r: 8, p: 1 },
+            KeystoreParams { log_n: 20, r: 1 << 20, p: 1 },
+            KeystoreParams { log_n: 20, r: 9, p: 1 },
+            KeystoreParams { log_n: 10, r: 8, p: u32::MAX },
+        ];
+        for params in oversized {
+            let (_, keystore) = keystore_with_params(params);
+            match decrypt_keypair(&keystore, "password") {
+                Err(KeystoreError::InvalidParameters(_)) => {}
+                other => panic!("{:?} accepted: {:?}", params, other.map(|keypair| keypair.pubkey())),
+            }
+        }
+    }
+
+    #[test]
+    fn accepts_the_largest_allowed_cost() {
+        for params in [
+            KeystoreParams { log_n: 20, r: 8, p: 1 },
+            KeystoreParams { log_n: 10, r: 8, p: 1024 },
+        ] {
+            assert_eq!(check_cost(&params), Ok(()));
+        }
+    }
+}
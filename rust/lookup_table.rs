@@ -0,0 +1,351 @@
+// Address lookup tables and v0 transactions.
+//
+// A legacy transaction lists every account as a 32-byte key, which caps it at roughly 35
+// accounts. A v0 transaction can instead reference accounts stored in on-chain lookup tables by
+// a one-byte index, so instructions with many accounts can be batched into one transaction.
+use crate::transaction::{apply_options, build_transaction, parse_blockhash, TransactionOptions};
+use crate::{parse_pubkey, KeypairError};
+use base64::decode;
+use solana_sdk::address_lookup_table::instruction as lookup_table_instruction;
+use solana_sdk::address_lookup_table::state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES};
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::clock::Slot;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+// Most addresses one extend transaction can carry while staying under the packet size limit
+pub const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+// An address lookup table account, as stored on chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTableInfo {
+    pub address: Pubkey,
+    // None once the table is frozen
+    pub authority: Option<Pubkey>,
+    // Slot::MAX while the table is active
+    pub deactivation_slot: Slot,
+    pub last_extended_slot: Slot,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl LookupTableInfo {
+    pub fn is_deactivated(&self) -> bool {
+        self.deactivation_slot != Slot::MAX
+    }
+
+    // The form the v0 message compiler takes
+    pub fn to_account(&self) -> AddressLookupTableAccount {
+        AddressLookupTableAccount {
+            key: self.address,
+            addresses: self.addresses.clone(),
+        }
+    }
+}
+
+// Read a lookup table from its base64 account data, as returned by getAccountInfo
+pub fn decode_lookup_table(address: &str, data_base64: &str) -> Result<LookupTableInfo, KeypairError> {
+    let address = parse_pubkey(address)?;
+    let data = decode(data_base64.trim())?;
+    let table =
+        AddressLookupTable::deserialize(&data).map_err(|e| KeypairError::InvalidLookupTable(e.to_string()))?;
+
+    Ok(LookupTableInfo {
+        address,
+        authority: table.meta.authority,
+        deactivation_slot: table.meta.deactivation_slot,
+        last_extended_slot: table.meta.last_extended_slot,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+// Address of the table `authority` creates at `recent_slot`
+pub fn derive_lookup_table_address(authority: &Pubkey, recent_slot: Slot) -> Pubkey {
+    Pubkey::find_program_address(
+        &[authority.as_ref(), &recent_slot.to_le_bytes()],
+        &solana_sdk::address_lookup_table::program::id(),
+    )
+    .0
+}
+
+// Create an empty lookup table owned by `authority`, paid for by `payer`.
+// `recent_slot` must be a slot the cluster still remembers, e.g. from getSlot with
+// "finalized" commitment; it is part of the table's address.
+pub fn build_create_lookup_table(
+    payer: &Keypair,
+    authority: &str,
+    recent_slot: Slot,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<(Transaction, Pubkey), KeypairError> {
+    let authority = parse_pubkey(authority)?;
+    let (instruction, lookup_table) =
+        lookup_table_instruction::create_lookup_table(authority, payer.pubkey(), recent_slot);
+    let transaction = build_transaction(payer, vec![instruction], recent_blockhash, options)?;
+    Ok((transaction, lookup_table))
+}
+
+// Append addresses to a table; the authority signs and pays for the extra space
+pub fn build_extend_lookup_table(
+    authority: &Keypair,
+    lookup_table: &str,
+    addresses: &[Pubkey],
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let lookup_table = parse_pubkey(lookup_table)?;
+    if addresses.is_empty() || addresses.len() > MAX_ADDRESSES_PER_EXTEND {
+        return Err(KeypairError::InvalidLookupTable(format!(
+            "can extend by 1 to {} addresses at a time, got {}",
+            MAX_ADDRESSES_PER_EXTEND,
+            addresses.len()
+        )));
+    }
+
+    let instruction = lookup_table_instruction::extend_lookup_table(
+        lookup_table,
+        authority.pubkey(),
+        Some(authority.pubkey()),
+        addresses.to_vec(),
+    );
+    build_transaction(authority, vec![instruction], recent_blockhash, options)
+}
+
+// Addresses not already in the table, split into batches of at most MAX_ADDRESSES_PER_EXTEND
+pub fn extend_batches(table: &LookupTableInfo, addresses: &[Pubkey]) -> Result<Vec<Vec<Pubkey>>, KeypairError> {
+    let mut missing: Vec<Pubkey> = Vec::new();
+    for address in addresses {
+        if !table.addresses.contains(address) && !missing.contains(address) {
+            missing.push(*address);
+        }
+    }
+    if table.addresses.len() + missing.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(KeypairError::InvalidLookupTable(format!(
+            "{} would hold {} addresses, more than the limit of {}",
+            table.address,
+            table.addresses.len() + missing.len(),
+            LOOKUP_TABLE_MAX_ADDRESSES
+        )));
+    }
+    Ok(missing
+        .chunks(MAX_ADDRESSES_PER_EXTEND)
+        .map(|chunk| chunk.to_vec())
+        .collect())
+}
+
+// Stop the table from being used or extended; it can be closed once the deactivation slot
+// is no longer recent, about 513 slots later
+pub fn build_deactivate_lookup_table(
+    authority: &Keypair,
+    lookup_table: &str,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let lookup_table = parse_pubkey(lookup_table)?;
+    let instruction = lookup_table_instruction::deactivate_lookup_table(lookup_table, authority.pubkey());
+    build_transaction(authority, vec![instruction], recent_blockhash, options)
+}
+
+// Close a deactivated table and send its rent to `recipient`
+pub fn build_close_lookup_table(
+    authority: &Keypair,
+    lookup_table: &str,
+    recipient: &str,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let lookup_table = parse_pubkey(lookup_table)?;
+    let recipient = parse_pubkey(recipient)?;
+    let instruction = lookup_table_instruction::close_lookup_table(lookup_table, authority.pubkey(), recipient);
+    build_transaction(authority, vec![instruction], recent_blockhash, options)
+}
+
+// Compile a v0 message. Every account that is neither a signer nor an invoked program and
+// appears in one of the tables is referenced through the table instead of listed inline.
+pub fn compile_v0_message(
+    fee_payer: &Pubkey,
+    instructions: Vec<Instruction>,
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<v0::Message, KeypairError> {
+    let blockhash = parse_blockhash(recent_blockhash)?;
+    let instructions = apply_options(fee_payer, instructions, options);
+    v0::Message::try_compile(fee_payer, &instructions, lookup_tables, blockhash)
+        .map_err(|e| KeypairError::InvalidTransaction(e.to_string()))
+}
+
+// Build and sign a v0 transaction, failing if it still does not fit in a packet
+pub fn build_v0_transaction(
+    payer: &Keypair,
+    other_signers: &[&Keypair],
+    instructions: Vec<Instruction>,
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<VersionedTransaction, KeypairError> {
+    let message = compile_v0_message(&payer.pubkey(), instructions, lookup_tables, recent_blockhash, options)?;
+
+    let mut signers = vec![payer];
+    signers.extend_from_slice(other_signers);
+    let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &signers)
+        .map_err(|e| KeypairError::SigningFailed(e.to_string()))?;
+
+    let size = bincode::serialized_size(&transaction).map_err(|e| KeypairError::InvalidTransaction(e.to_string()))?;
+    if size as usize > PACKET_DATA_SIZE {
+        return Err(KeypairError::InvalidTransaction(format!(
+            "transaction is {} bytes, over the {} byte limit",
+            size, PACKET_DATA_SIZE
+        )));
+    }
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::address_lookup_table::state::LookupTableMeta;
+    use solana_sdk::signature::keypair_from_seed;
+    use solana_sdk::system_instruction;
+    use std::borrow::Cow;
+
+    const BLOCKHASH: &str = "8ojkxBFz8f1PZYJvrDc5wBR1WBSGXLFUrgnt1cSN1nSW";
+
+    fn keypair() -> Keypair {
+        keypair_from_seed(&[1; 32]).unwrap()
+    }
+
+    fn addresses(count: usize) -> Vec<Pubkey> {
+        (0..count).map(|_| Pubkey::new_unique()).collect()
+    }
+
+    fn table(addresses: Vec<Pubkey>) -> LookupTableInfo {
+        LookupTableInfo {
+            address: derive_lookup_table_address(&keypair().pubkey(), 100),
+            authority: Some(keypair().pubkey()),
+            deactivation_slot: Slot::MAX,
+            last_extended_slot: 0,
+            addresses,
+        }
+    }
+
+    // A transfer to each recipient, too many accounts for a legacy transaction
+    fn transfers(recipients: &[Pubkey]) -> Vec<Instruction> {
+        recipients
+            .iter()
+            .map(|recipient| system_instruction::transfer(&keypair().pubkey(), recipient, 1))
+            .collect()
+    }
+
+    #[test]
+    fn extend_batches_skip_known_addresses_and_chunk() {
+        let known = addresses(3);
+        let new = addresses(45);
+        let mut wanted = known.clone();
+        wanted.extend(&new);
+        wanted.extend(&new[..5]);
+
+        let batches = extend_batches(&table(known), &wanted).unwrap();
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![MAX_ADDRESSES_PER_EXTEND, MAX_ADDRESSES_PER_EXTEND, 5]);
+        assert_eq!(batches.concat(), new);
+
+        assert!(extend_batches(&table(addresses(3)), &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn extend_batches_reject_overfull_table() {
+        let full = table(addresses(LOOKUP_TABLE_MAX_ADDRESSES - 2));
+        assert_eq!(extend_batches(&full, &addresses(2)).unwrap().len(), 1);
+        assert!(matches!(extend_batches(&full, &addresses(3)), Err(KeypairError::InvalidLookupTable(_))));
+    }
+
+    #[test]
+    fn full_extend_fits_in_a_packet() {
+        let authority = keypair();
+        let address = table(Vec::new()).address.to_string();
+        let options = TransactionOptions::default();
+
+        let transaction =
+            build_extend_lookup_table(&authority, &address, &addresses(MAX_ADDRESSES_PER_EXTEND), BLOCKHASH, &options)
+                .unwrap();
+        assert!(bincode::serialized_size(&transaction).unwrap() as usize <= PACKET_DATA_SIZE);
+
+        for count in [0, MAX_ADDRESSES_PER_EXTEND + 1] {
+            let result = build_extend_lookup_table(&authority, &address, &addresses(count), BLOCKHASH, &options);
+            assert!(matches!(result, Err(KeypairError::InvalidLookupTable(_))), "{} addresses", count);
+        }
+    }
+
+    #[test]
+    fn create_uses_derived_address() {
+        let payer = keypair();
+        let authority = Pubkey::new_unique();
+        let options = TransactionOptions::default();
+        let (_, lookup_table) =
+            build_create_lookup_table(&payer, &authority.to_string(), 100, BLOCKHASH, &options).unwrap();
+        assert_eq!(lookup_table, derive_lookup_table_address(&authority, 100));
+    }
+
+    #[test]
+    fn v0_transaction_resolves_accounts_through_lookup_table() {
+        let payer = keypair();
+        let recipients = addresses(40);
+        let lookup_tables = [table(recipients.clone()).to_account()];
+        let lookup_table = &lookup_tables[0];
+        let options = TransactionOptions::default();
+
+        let transaction =
+            build_v0_transaction(&payer, &[], transfers(&recipients), &lookup_tables, BLOCKHASH, &options).unwrap();
+        let VersionedMessage::V0(message) = &transaction.message else {
+            panic!("expected a v0 message");
+        };
+
+        // Only the payer and the system program stay inline
+        assert_eq!(message.account_keys, vec![payer.pubkey(), solana_sdk::system_program::id()]);
+        assert_eq!(message.address_table_lookups.len(), 1);
+        let lookup = &message.address_table_lookups[0];
+        assert_eq!(lookup.account_key, lookup_table.key);
+        assert_eq!(lookup.writable_indexes, (0..40).collect::<Vec<u8>>());
+        assert!(lookup.readonly_indexes.is_empty());
+        assert!(transaction.verify_with_results().iter().all(|ok| *ok));
+
+        // Without the table the same transfers are too big for one packet
+        let result = build_v0_transaction(&payer, &[], transfers(&recipients), &[], BLOCKHASH, &options);
+        match result {
+            Err(KeypairError::InvalidTransaction(reason)) => assert!(reason.contains("byte limit"), "{}", reason),
+            other => panic!("oversized transaction accepted: {:?}", other.map(|tx| tx.signatures)),
+        }
+    }
+
+    #[test]
+    fn decodes_lookup_table_account() {
+        let authority = Pubkey::new_unique();
+        let entries = addresses(3);
+        let table = AddressLookupTable {
+            meta: LookupTableMeta {
+                authority: Some(authority),
+                last_extended_slot: 42,
+                ..LookupTableMeta::default()
+            },
+            addresses: Cow::Owned(entries.clone()),
+        };
+        let data = base64::encode(table.serialize_for_tests().unwrap());
+
+        let address = Pubkey::new_unique();
+        let info = decode_lookup_table(&address.to_string(), &data).unwrap();
+        assert_eq!(info.address, address);
+        assert_eq!(info.authority, Some(authority));
+        assert_eq!(info.last_extended_slot, 42);
+        assert_eq!(info.addresses, entries);
+        assert!(!info.is_deactivated());
+
+        assert!(matches!(
+            decode_lookup_table(&address.to_string(), &base64::encode([1, 2, 3])),
+            Err(KeypairError::InvalidLookupTable(_))
+        ));
+    }
+}
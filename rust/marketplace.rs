@@ -0,0 +1,4 @@
+// The token marketplace program's instruction and account layouts, taken from the program crate
+// (see marketplace/) so clients decode exactly what the program reads and writes.
+pub use solana_token_marketplace::instruction::MarketplaceInstruction;
+pub use solana_token_marketplace::state::{Marketplace, SellOrder, SellOrderV1, SellOrderV2, SellerState};
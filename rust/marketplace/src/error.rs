@@ -0,0 +1,46 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone)]
+pub enum MarketplaceError {
+    #[error("Invalid instruction")]
+    InvalidInstruction,
+    #[error("Not authorized")]
+    NotAuthorized,
+    #[error("Already initialized")]
+    AlreadyInitialized,
+    #[error("Not initialized")]
+    NotInitialized,
+    #[error("Invalid amount")]
+    InvalidAmount,
+    #[error("Insufficient funds")]
+    InsufficientFunds,
+    #[error("Order not active")]
+    OrderNotActive,
+    #[error("Invalid fee percentage")]
+    InvalidFeePercentage,
+    #[error("Numerical overflow")]
+    NumericalOverflow,
+    #[error("Invalid token account")]
+    InvalidTokenAccount,
+    #[error("Invalid mint")]
+    InvalidMint,
+    #[error("Seller does not match the order")]
+    InvalidSeller,
+    #[error("Admin does not match the marketplace")]
+    InvalidAdmin,
+    #[error("Order belongs to a different marketplace")]
+    InvalidMarketplace,
+    #[error("Order is still active")]
+    OrderStillActive,
+    #[error("Order address does not match the seller's next order")]
+    InvalidOrderAddress,
+    #[error("Invalid seller state account")]
+    InvalidSellerState,
+}
+
+impl From<MarketplaceError> for ProgramError {
+    fn from(e: MarketplaceError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
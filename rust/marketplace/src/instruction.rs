@@ -0,0 +1,204 @@
+use crate::state::{find_order_address, find_seller_state_address, find_vault_address};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{instruction::AccountMeta, instruction::Instruction, pubkey::Pubkey, system_program};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum MarketplaceInstruction {
+    /// Initialize marketplace
+    /// Accounts:
+    /// 0. [signer] Admin account
+    /// 1. [writable, signer] Marketplace account, created by this instruction
+    /// 2. [] System program
+    InitializeMarketplace { fee_percentage: u16 },
+
+    /// Create sell order, moving `amount` tokens into the order's vault
+    /// Accounts:
+    /// 0. [signer] Seller account
+    /// 1. [writable] Seller token account
+    /// 2. [writable] Order account, the PDA ["sell_order", marketplace, seller, mint, nonce],
+    ///    created by this instruction
+    /// 3. [writable] Order vault, the PDA ["vault", order], created by this instruction
+    /// 4. [] Marketplace account
+    /// 5. [writable] Seller state, the PDA ["seller", marketplace, seller], created with the
+    ///    seller's first order
+    /// 6. [] Token mint
+    /// 7. [] System program
+    /// 8. [] Token program
+    CreateSellOrder { amount: u64, price: u64 },
+
+    /// Buy tokens
+    /// Accounts:
+    /// 0. [signer] Buyer account
+    /// 1. [writable] Buyer token account
+    /// 2. [writable] Seller account
+    /// 3. [writable] Order vault
+    /// 4. [writable] Order account
+    /// 5. [writable] Marketplace account
+    /// 6. [writable] Admin account
+    /// 7. [] Token mint
+    /// 8. [] System program
+    /// 9. [] Token program
+    /// 10. [] Associated token program
+    ///
+    /// Filling the order closes it and its vault, refunding their rent to the seller.
+    BuyTokens { amount: u64 },
+
+    /// Cancel sell order, returning the tokens left in its vault to the seller and closing the
+    /// order and its vault
+    /// Accounts:
+    /// 0. [signer] Seller account
+    /// 1. [writable] Seller token account
+    /// 2. [writable] Order account
+    /// 3. [writable] Order vault
+    /// 4. [] Token program
+    CancelOrder,
+
+    /// Update order price
+    /// Accounts:
+    /// 0. [signer] Seller account
+    /// 1. [writable] Order account
+    UpdatePrice { new_price: u64 },
+
+    /// Close inactive orders left open by earlier versions of the program, refunding their
+    /// rent to the sellers. Orders in the V1 layout record no marketplace, so the admin of
+    /// any marketplace, including one initialized just for this, may sweep them. Only
+    /// inactive orders are swept and the rent can only go to the recorded seller. The vault
+    /// address is always checked, and may hold no account only for V1 orders listed before
+    /// escrow.
+    /// Accounts:
+    /// 0. [signer] Admin account
+    /// 1. [] Marketplace account
+    /// 2. [] Token program
+    /// 3. [writable] Order account, order vault and seller account of each order, in turn
+    SweepOrders,
+}
+
+pub fn initialize_marketplace(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    marketplace: &Pubkey,
+    fee_percentage: u16,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(*marketplace, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: MarketplaceInstruction::InitializeMarketplace { fee_percentage }
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+// `nonce` is the seller's current order count, 0 before their first order in the marketplace
+#[allow(clippy::too_many_arguments)]
+pub fn create_sell_order(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    seller_token_account: &Pubkey,
+    marketplace: &Pubkey,
+    token_mint: &Pubkey,
+    nonce: u64,
+    amount: u64,
+    price: u64,
+) -> Instruction {
+    let (order, _) = find_order_address(program_id, marketplace, seller, token_mint, nonce);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*seller, true),
+            AccountMeta::new(*seller_token_account, false),
+            AccountMeta::new(order, false),
+            AccountMeta::new(find_vault_address(program_id, &order).0, false),
+            AccountMeta::new_readonly(*marketplace, false),
+            AccountMeta::new(find_seller_state_address(program_id, marketplace, seller).0, false),
+            AccountMeta::new_readonly(*token_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: MarketplaceInstruction::CreateSellOrder { amount, price }
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn buy_tokens(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    buyer_token_account: &Pubkey,
+    seller: &Pubkey,
+    order: &Pubkey,
+    marketplace: &Pubkey,
+    admin: &Pubkey,
+    token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*buyer, true),
+            AccountMeta::new(*buyer_token_account, false),
+            AccountMeta::new(*seller, false),
+            AccountMeta::new(find_vault_address(program_id, order).0, false),
+            AccountMeta::new(*order, false),
+            AccountMeta::new(*marketplace, false),
+            AccountMeta::new(*admin, false),
+            AccountMeta::new_readonly(*token_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+        data: MarketplaceInstruction::BuyTokens { amount }
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+pub fn cancel_order(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    seller_token_account: &Pubkey,
+    order: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*seller, true),
+            AccountMeta::new(*seller_token_account, false),
+            AccountMeta::new(*order, false),
+            AccountMeta::new(find_vault_address(program_id, order).0, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: MarketplaceInstruction::CancelOrder.try_to_vec().unwrap(),
+    }
+}
+
+pub fn update_price(program_id: &Pubkey, seller: &Pubkey, order: &Pubkey, new_price: u64) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*seller, true), AccountMeta::new(*order, false)],
+        data: MarketplaceInstruction::UpdatePrice { new_price }.try_to_vec().unwrap(),
+    }
+}
+
+// `orders` are (order, seller) pairs
+pub fn sweep_orders(program_id: &Pubkey, admin: &Pubkey, marketplace: &Pubkey, orders: &[(Pubkey, Pubkey)]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new_readonly(*marketplace, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    for (order, seller) in orders {
+        accounts.push(AccountMeta::new(*order, false));
+        accounts.push(AccountMeta::new(find_vault_address(program_id, order).0, false));
+        accounts.push(AccountMeta::new(*seller, false));
+    }
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: MarketplaceInstruction::SweepOrders.try_to_vec().unwrap(),
+    }
+}
@@ -0,0 +1,24 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
+
+pub mod error;
+pub mod instruction;
+pub mod processor;
+pub mod state;
+pub mod utils;
+
+use crate::processor::Processor;
+
+// Replace with the address of the program keypair this is deployed with
+solana_program::declare_id!("8ApE6yzQULBKQttZRrvHGRHsckDmyn18dPX2PKPAecE9");
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    msg!("Solana Token Marketplace: Processing instruction");
+    Processor::process(program_id, accounts, instruction_data)
+}
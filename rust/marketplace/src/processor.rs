@@ -0,0 +1,699 @@
+use crate::{
+    error::MarketplaceError,
+    instruction::MarketplaceInstruction,
+    state::{
+        find_order_address, find_seller_state_address, find_vault_address, Marketplace, SellOrder, SellOrderV1,
+        SellOrderV2, SellerState, ORDER_SEED, SELLER_SEED, VAULT_SEED,
+    },
+    utils::*,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    system_program,
+    sysvar::Sysvar,
+    clock::Clock,
+};
+use spl_token::state::Account as TokenAccount;
+
+pub struct Processor;
+
+impl Processor {
+    pub fn process(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+    ) -> ProgramResult {
+        let instruction = MarketplaceInstruction::try_from_slice(instruction_data)
+            .map_err(|_| MarketplaceError::InvalidInstruction)?;
+
+        match instruction {
+            MarketplaceInstruction::InitializeMarketplace { fee_percentage } => {
+                Self::process_initialize_marketplace(program_id, accounts, fee_percentage)
+            }
+            MarketplaceInstruction::CreateSellOrder { amount, price } => {
+                Self::process_create_sell_order(program_id, accounts, amount, price)
+            }
+            MarketplaceInstruction::BuyTokens { amount } => {
+                Self::process_buy_tokens(program_id, accounts, amount)
+            }
+            MarketplaceInstruction::CancelOrder => {
+                Self::process_cancel_order(program_id, accounts)
+            }
+            MarketplaceInstruction::UpdatePrice { new_price } => {
+                Self::process_update_price(program_id, accounts, new_price)
+            }
+            MarketplaceInstruction::SweepOrders => {
+                Self::process_sweep_orders(program_id, accounts)
+            }
+        }
+    }
+
+    fn process_initialize_marketplace(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fee_percentage: u16,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let marketplace_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if !admin_info.is_signer {
+            return Err(MarketplaceError::NotAuthorized.into());
+        }
+
+        assert_program_id(system_program_info, &system_program::id())?;
+
+        if marketplace_info.owner == program_id {
+            return Err(MarketplaceError::AlreadyInitialized.into());
+        }
+
+        if fee_percentage > 1000 {
+            // Max 10%
+            return Err(MarketplaceError::InvalidFeePercentage.into());
+        }
+
+        let rent = Rent::get()?;
+        let space = Marketplace::LEN;
+        let lamports = rent.minimum_balance(space);
+
+        invoke(
+            &system_instruction::create_account(
+                admin_info.key,
+                marketplace_info.key,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &[admin_info.clone(), marketplace_info.clone(), system_program_info.clone()],
+        )?;
+
+        let marketplace = Marketplace {
+            admin: *admin_info.key,
+            fee_percentage,
+            total_volume: 0,
+            total_fees_collected: 0,
+            is_initialized: true,
+        };
+
+        marketplace.serialize(&mut &mut marketplace_info.try_borrow_mut_data()?[..])?;
+
+        msg!("Marketplace initialized with fee: {}%", fee_percentage as f64 / 100.0);
+        Ok(())
+    }
+
+    fn process_create_sell_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        price: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let seller_info = next_account_info(account_info_iter)?;
+        let seller_token_account_info = next_account_info(account_info_iter)?;
+        let order_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let marketplace_info = next_account_info(account_info_iter)?;
+        let seller_state_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !seller_info.is_signer {
+            return Err(MarketplaceError::NotAuthorized.into());
+        }
+
+        if amount == 0 || price == 0 {
+            return Err(MarketplaceError::InvalidAmount.into());
+        }
+
+        assert_program_id(system_program_info, &system_program::id())?;
+        assert_program_id(token_program_info, &spl_token::id())?;
+        Self::load_marketplace(program_id, marketplace_info)?;
+        assert_owned_by(token_mint_info, &spl_token::id())?;
+
+        // Verify token account
+        let seller_token_account = Self::load_token_account(seller_token_account_info, token_mint_info.key)?;
+        assert_token_account_owner(&seller_token_account, seller_info.key)?;
+
+        if seller_token_account.amount < amount {
+            return Err(MarketplaceError::InsufficientFunds.into());
+        }
+
+        let clock = Clock::get()?;
+
+        let (seller_state_address, seller_state_bump) =
+            find_seller_state_address(program_id, marketplace_info.key, seller_info.key);
+        if seller_state_address != *seller_state_info.key {
+            return Err(MarketplaceError::InvalidSellerState.into());
+        }
+
+        let mut seller_state = if seller_state_info.owner == program_id {
+            SellerState::try_from_slice(&seller_state_info.try_borrow_data()?)?
+        } else {
+            Self::create_pda_account(
+                seller_info,
+                seller_state_info,
+                SellerState::LEN,
+                program_id,
+                system_program_info,
+                &[SELLER_SEED, marketplace_info.key.as_ref(), seller_info.key.as_ref(), &[seller_state_bump]],
+            )?;
+            SellerState {
+                marketplace: *marketplace_info.key,
+                seller: *seller_info.key,
+                order_count: 0,
+            }
+        };
+
+        // The order lives at the address of the seller's next nonce
+        let nonce = seller_state.order_count;
+        let (order_address, order_bump) =
+            find_order_address(program_id, marketplace_info.key, seller_info.key, token_mint_info.key, nonce);
+        if order_address != *order_info.key {
+            return Err(MarketplaceError::InvalidOrderAddress.into());
+        }
+
+        Self::create_pda_account(
+            seller_info,
+            order_info,
+            SellOrder::LEN,
+            program_id,
+            system_program_info,
+            &[
+                ORDER_SEED,
+                marketplace_info.key.as_ref(),
+                seller_info.key.as_ref(),
+                token_mint_info.key.as_ref(),
+                &nonce.to_le_bytes(),
+                &[order_bump],
+            ],
+        )?;
+
+        // Escrow the listed tokens so the seller cannot spend them while the order is open
+        let (vault, vault_bump) = find_vault_address(program_id, order_info.key);
+        if vault != *vault_info.key {
+            return Err(MarketplaceError::InvalidTokenAccount.into());
+        }
+
+        Self::create_pda_account(
+            seller_info,
+            vault_info,
+            TokenAccount::LEN,
+            token_program_info.key,
+            system_program_info,
+            &[VAULT_SEED, order_info.key.as_ref(), &[vault_bump]],
+        )?;
+
+        invoke(
+            &spl_token::instruction::initialize_account3(
+                token_program_info.key,
+                vault_info.key,
+                token_mint_info.key,
+                vault_info.key,
+            )?,
+            &[vault_info.clone(), token_mint_info.clone(), token_program_info.clone()],
+        )?;
+
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                seller_token_account_info.key,
+                vault_info.key,
+                seller_info.key,
+                &[],
+                amount,
+            )?,
+            &[
+                seller_token_account_info.clone(),
+                vault_info.clone(),
+                seller_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+
+        let sell_order = SellOrder {
+            marketplace: *marketplace_info.key,
+            seller: *seller_info.key,
+            token_mint: *token_mint_info.key,
+            nonce,
+            amount,
+            price_per_token: price,
+            created_at: clock.unix_timestamp,
+            is_active: true,
+        };
+
+        sell_order.serialize(&mut &mut order_info.try_borrow_mut_data()?[..])?;
+
+        seller_state.order_count = nonce.checked_add(1).ok_or(MarketplaceError::NumericalOverflow)?;
+        seller_state.serialize(&mut &mut seller_state_info.try_borrow_mut_data()?[..])?;
+
+        msg!("Sell order created: {} tokens at {} lamports each", amount, price);
+        Ok(())
+    }
+
+    fn process_buy_tokens(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let buyer_info = next_account_info(account_info_iter)?;
+        let buyer_token_account_info = next_account_info(account_info_iter)?;
+        let seller_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let order_info = next_account_info(account_info_iter)?;
+        let marketplace_info = next_account_info(account_info_iter)?;
+        let admin_info = next_account_info(account_info_iter)?;
+        let token_mint_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let associated_token_program_info = next_account_info(account_info_iter)?;
+
+        if !buyer_info.is_signer {
+            return Err(MarketplaceError::NotAuthorized.into());
+        }
+
+        assert_program_id(system_program_info, &system_program::id())?;
+        assert_program_id(token_program_info, &spl_token::id())?;
+        assert_program_id(associated_token_program_info, &spl_associated_token_account::id())?;
+
+        let mut order = Self::load_order(program_id, order_info)?;
+        let mut marketplace = Self::load_marketplace(program_id, marketplace_info)?;
+
+        // Payments go where the order and marketplace say, not where the buyer says
+        if order.marketplace != *marketplace_info.key {
+            return Err(MarketplaceError::InvalidMarketplace.into());
+        }
+
+        if order.seller != *seller_info.key {
+            return Err(MarketplaceError::InvalidSeller.into());
+        }
+
+        if marketplace.admin != *admin_info.key {
+            return Err(MarketplaceError::InvalidAdmin.into());
+        }
+
+        if order.token_mint != *token_mint_info.key {
+            return Err(MarketplaceError::InvalidMint.into());
+        }
+
+        Self::load_token_account(buyer_token_account_info, &order.token_mint)?;
+
+        if !order.is_active {
+            return Err(MarketplaceError::OrderNotActive.into());
+        }
+
+        if amount > order.amount {
+            return Err(MarketplaceError::InvalidAmount.into());
+        }
+
+        let total_price = order.calculate_total_price(amount)
+            .ok_or(MarketplaceError::NumericalOverflow)?;
+        let fee = order.calculate_fee(amount, marketplace.fee_percentage)
+            .ok_or(MarketplaceError::NumericalOverflow)?;
+        let seller_amount = total_price.checked_sub(fee)
+            .ok_or(MarketplaceError::NumericalOverflow)?;
+
+        if buyer_info.lamports() < total_price {
+            return Err(MarketplaceError::InsufficientFunds.into());
+        }
+
+        // Transfer SOL from buyer to seller
+        invoke(
+            &system_instruction::transfer(buyer_info.key, seller_info.key, seller_amount),
+            &[buyer_info.clone(), seller_info.clone(), system_program_info.clone()],
+        )?;
+
+        // Transfer fee to admin
+        if fee > 0 {
+            invoke(
+                &system_instruction::transfer(buyer_info.key, admin_info.key, fee),
+                &[buyer_info.clone(), admin_info.clone(), system_program_info.clone()],
+            )?;
+        }
+
+        // Transfer tokens from the order's vault to buyer
+        Self::transfer_from_vault(
+            program_id,
+            order_info,
+            vault_info,
+            buyer_token_account_info,
+            token_program_info,
+            amount,
+        )?;
+
+        // Update order, closing it and its vault once filled
+        order.amount = order.amount.checked_sub(amount)
+            .ok_or(MarketplaceError::NumericalOverflow)?;
+
+        if order.amount == 0 {
+            Self::close_vault(program_id, order_info, vault_info, seller_info, token_program_info)?;
+            Self::close_order(order_info, seller_info)?;
+        } else {
+            order.serialize(&mut &mut order_info.try_borrow_mut_data()?[..])?;
+        }
+
+        // Update marketplace stats
+        marketplace.total_volume = marketplace.total_volume.checked_add(total_price)
+            .ok_or(MarketplaceError::NumericalOverflow)?;
+        marketplace.total_fees_collected = marketplace.total_fees_collected.checked_add(fee)
+            .ok_or(MarketplaceError::NumericalOverflow)?;
+
+        marketplace.serialize(&mut &mut marketplace_info.try_borrow_mut_data()?[..])?;
+
+        msg!("Tokens purchased: {} for {} lamports", amount, total_price);
+        Ok(())
+    }
+
+    fn process_cancel_order(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let seller_info = next_account_info(account_info_iter)?;
+        let seller_token_account_info = next_account_info(account_info_iter)?;
+        let order_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !seller_info.is_signer {
+            return Err(MarketplaceError::NotAuthorized.into());
+        }
+
+        assert_program_id(token_program_info, &spl_token::id())?;
+
+        let order = Self::load_order(program_id, order_info)?;
+
+        if order.seller != *seller_info.key {
+            return Err(MarketplaceError::NotAuthorized.into());
+        }
+
+        if !order.is_active {
+            return Err(MarketplaceError::OrderNotActive.into());
+        }
+
+        let seller_token_account = Self::load_token_account(seller_token_account_info, &order.token_mint)?;
+        assert_token_account_owner(&seller_token_account, seller_info.key)?;
+
+        // Return whatever is left in escrow
+        Self::transfer_from_vault(
+            program_id,
+            order_info,
+            vault_info,
+            seller_token_account_info,
+            token_program_info,
+            order.amount,
+        )?;
+
+        Self::close_vault(program_id, order_info, vault_info, seller_info, token_program_info)?;
+        Self::close_order(order_info, seller_info)?;
+
+        msg!("Sell order cancelled");
+        Ok(())
+    }
+
+    fn process_update_price(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_price: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let seller_info = next_account_info(account_info_iter)?;
+        let order_info = next_account_info(account_info_iter)?;
+
+        if !seller_info.is_signer {
+            return Err(MarketplaceError::NotAuthorized.into());
+        }
+
+        if new_price == 0 {
+            return Err(MarketplaceError::InvalidAmount.into());
+        }
+
+        let mut order = Self::load_order(program_id, order_info)?;
+
+        if order.seller != *seller_info.key {
+            return Err(MarketplaceError::NotAuthorized.into());
+        }
+
+        if !order.is_active {
+            return Err(MarketplaceError::OrderNotActive.into());
+        }
+
+        order.price_per_token = new_price;
+        order.serialize(&mut &mut order_info.try_borrow_mut_data()?[..])?;
+
+        msg!("Order price updated to {} lamports", new_price);
+        Ok(())
+    }
+
+    fn process_sweep_orders(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin_info = next_account_info(account_info_iter)?;
+        let marketplace_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if !admin_info.is_signer {
+            return Err(MarketplaceError::NotAuthorized.into());
+        }
+
+        assert_program_id(token_program_info, &spl_token::id())?;
+
+        let marketplace = Self::load_marketplace(program_id, marketplace_info)?;
+        if marketplace.admin != *admin_info.key {
+            return Err(MarketplaceError::InvalidAdmin.into());
+        }
+
+        let order_accounts = account_info_iter.as_slice();
+        let orders = order_accounts.chunks_exact(3);
+        if order_accounts.is_empty() || !orders.remainder().is_empty() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        for accounts in orders.clone() {
+            let (order_info, vault_info, seller_info) = (&accounts[0], &accounts[1], &accounts[2]);
+            let (seller, marketplace, is_active) = Self::load_any_order(program_id, order_info)?;
+
+            // V1 orders predate marketplaces being recorded, so any admin may sweep them. This is
+            // intended: only inactive orders are swept and the rent always goes to their seller.
+            if marketplace.is_some_and(|marketplace| marketplace != *marketplace_info.key) {
+                return Err(MarketplaceError::InvalidMarketplace.into());
+            }
+
+            if seller != *seller_info.key {
+                return Err(MarketplaceError::InvalidSeller.into());
+            }
+
+            if is_active {
+                return Err(MarketplaceError::OrderStillActive.into());
+            }
+
+            // V1 orders listed before escrow have no vault, every later order has one. The vault
+            // is always checked, as its rent could never be recovered once the order is gone.
+            Self::vault_bump(program_id, order_info, vault_info)?;
+            if vault_info.owner == &spl_token::id() {
+                Self::close_vault(program_id, order_info, vault_info, seller_info, token_program_info)?;
+            } else if marketplace.is_some() {
+                return Err(MarketplaceError::InvalidTokenAccount.into());
+            }
+        }
+
+        // Vaults are all closed through the token program before any lamports move directly
+        for accounts in orders {
+            Self::close_order(&accounts[0], &accounts[2])?;
+        }
+
+        msg!("Swept {} inactive orders", order_accounts.len() / 3);
+        Ok(())
+    }
+
+    // A marketplace account created and initialized by this program
+    fn load_marketplace(program_id: &Pubkey, marketplace_info: &AccountInfo) -> Result<Marketplace, ProgramError> {
+        assert_owned_by(marketplace_info, program_id)?;
+        assert_rent_exempt(&Rent::get()?, marketplace_info)?;
+        let marketplace = Marketplace::try_from_slice(&marketplace_info.try_borrow_data()?)?;
+        if !marketplace.is_initialized {
+            return Err(MarketplaceError::NotInitialized.into());
+        }
+        Ok(marketplace)
+    }
+
+    // A sell order account created by this program
+    fn load_order(program_id: &Pubkey, order_info: &AccountInfo) -> Result<SellOrder, ProgramError> {
+        assert_owned_by(order_info, program_id)?;
+        assert_rent_exempt(&Rent::get()?, order_info)?;
+        Ok(SellOrder::try_from_slice(&order_info.try_borrow_data()?)?)
+    }
+
+    // Create a rent-exempt PDA of `space` bytes owned by `owner`, paid for by `payer`. PDAs are
+    // predictable, and anyone can send lamports to one before it exists, which would make
+    // `create_account` fail for good. A funded address is topped up, allocated and assigned instead.
+    fn create_pda_account<'a>(
+        payer_info: &AccountInfo<'a>,
+        account_info: &AccountInfo<'a>,
+        space: usize,
+        owner: &Pubkey,
+        system_program_info: &AccountInfo<'a>,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        let required_lamports = Rent::get()?.minimum_balance(space);
+
+        if account_info.lamports() == 0 {
+            return invoke_signed(
+                &system_instruction::create_account(
+                    payer_info.key,
+                    account_info.key,
+                    required_lamports,
+                    space as u64,
+                    owner,
+                ),
+                &[payer_info.clone(), account_info.clone(), system_program_info.clone()],
+                &[seeds],
+            );
+        }
+
+        let top_up = required_lamports.saturating_sub(account_info.lamports());
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, account_info.key, top_up),
+                &[payer_info.clone(), account_info.clone(), system_program_info.clone()],
+            )?;
+        }
+
+        invoke_signed(
+            &system_instruction::allocate(account_info.key, space as u64),
+            &[account_info.clone(), system_program_info.clone()],
+            &[seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(account_info.key, owner),
+            &[account_info.clone(), system_program_info.clone()],
+            &[seeds],
+        )
+    }
+
+    // The seller, marketplace (where the layout records one) and active flag of an order in the
+    // current layout or one written by an earlier version of the program, told apart by size
+    fn load_any_order(
+        program_id: &Pubkey,
+        order_info: &AccountInfo,
+    ) -> Result<(Pubkey, Option<Pubkey>, bool), ProgramError> {
+        assert_owned_by(order_info, program_id)?;
+        assert_rent_exempt(&Rent::get()?, order_info)?;
+        let data = order_info.try_borrow_data()?;
+        match data.len() {
+            SellOrder::LEN => {
+                let order = SellOrder::try_from_slice(&data)?;
+                Ok((order.seller, Some(order.marketplace), order.is_active))
+            }
+            SellOrderV2::LEN => {
+                let order = SellOrderV2::try_from_slice(&data)?;
+                Ok((order.seller, Some(order.marketplace), order.is_active))
+            }
+            SellOrderV1::LEN => {
+                let order = SellOrderV1::try_from_slice(&data)?;
+                Ok((order.seller, None, order.is_active))
+            }
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    // An SPL token account holding `mint`
+    fn load_token_account(token_account_info: &AccountInfo, mint: &Pubkey) -> Result<TokenAccount, ProgramError> {
+        assert_owned_by(token_account_info, &spl_token::id())?;
+        let token_account = get_token_account(token_account_info)?;
+        if token_account.mint != *mint {
+            return Err(MarketplaceError::InvalidMint.into());
+        }
+        Ok(token_account)
+    }
+
+    // The vault's bump seed, checking that `vault_info` is the order's vault
+    fn vault_bump(program_id: &Pubkey, order_info: &AccountInfo, vault_info: &AccountInfo) -> Result<u8, ProgramError> {
+        let (vault, vault_bump) = find_vault_address(program_id, order_info.key);
+        if vault != *vault_info.key {
+            return Err(MarketplaceError::InvalidTokenAccount.into());
+        }
+        Ok(vault_bump)
+    }
+
+    // Move tokens out of an order's vault, signing as the vault
+    fn transfer_from_vault<'a>(
+        program_id: &Pubkey,
+        order_info: &AccountInfo<'a>,
+        vault_info: &AccountInfo<'a>,
+        destination_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        let vault_bump = Self::vault_bump(program_id, order_info, vault_info)?;
+
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                vault_info.key,
+                destination_info.key,
+                vault_info.key,
+                &[],
+                amount,
+            )?,
+            &[
+                vault_info.clone(),
+                destination_info.clone(),
+                vault_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&[VAULT_SEED, order_info.key.as_ref(), &[vault_bump]]],
+        )
+    }
+
+    // Close an empty vault, sending its rent to `destination`
+    fn close_vault<'a>(
+        program_id: &Pubkey,
+        order_info: &AccountInfo<'a>,
+        vault_info: &AccountInfo<'a>,
+        destination_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let vault_bump = Self::vault_bump(program_id, order_info, vault_info)?;
+
+        invoke_signed(
+            &spl_token::instruction::close_account(
+                token_program_info.key,
+                vault_info.key,
+                destination_info.key,
+                vault_info.key,
+                &[],
+            )?,
+            &[
+                vault_info.clone(),
+                destination_info.clone(),
+                vault_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&[VAULT_SEED, order_info.key.as_ref(), &[vault_bump]]],
+        )
+    }
+
+    // Close an order account, sending its rent to `destination`. The runtime removes the
+    // account once the transaction ends with it holding no lamports. Until then it is handed
+    // back to the system program with no data, so a later instruction in the same transaction
+    // cannot refund it and use it as an order again.
+    fn close_order(order_info: &AccountInfo, destination_info: &AccountInfo) -> ProgramResult {
+        let lamports = destination_info
+            .lamports()
+            .checked_add(order_info.lamports())
+            .ok_or(MarketplaceError::NumericalOverflow)?;
+        **destination_info.try_borrow_mut_lamports()? = lamports;
+        **order_info.try_borrow_mut_lamports()? = 0;
+        order_info.realloc(0, false)?;
+        order_info.assign(&system_program::id());
+        Ok(())
+    }
+}
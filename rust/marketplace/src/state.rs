@@ -0,0 +1,117 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct Marketplace {
+    pub admin: Pubkey,
+    pub fee_percentage: u16, // Basis points (100 = 1%)
+    pub total_volume: u64,
+    pub total_fees_collected: u64,
+    pub is_initialized: bool,
+}
+
+impl Marketplace {
+    pub const LEN: usize = 32 + 2 + 8 + 8 + 1;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct SellOrder {
+    pub marketplace: Pubkey,
+    pub seller: Pubkey,
+    pub token_mint: Pubkey,
+    pub nonce: u64, // The seller's order counter when the order was created
+    pub amount: u64,
+    pub price_per_token: u64, // In lamports
+    pub created_at: i64,
+    pub is_active: bool,
+}
+
+impl SellOrder {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    pub fn calculate_total_price(&self, amount: u64) -> Option<u64> {
+        self.price_per_token.checked_mul(amount)
+    }
+
+    pub fn calculate_fee(&self, amount: u64, fee_percentage: u16) -> Option<u64> {
+        let total_price = self.calculate_total_price(amount)?;
+        total_price.checked_mul(fee_percentage as u64)?.checked_div(10000)
+    }
+}
+
+// Sell order layouts written by earlier versions of the program, which left orders open once
+// they were filled or cancelled. They are only read to sweep those orders.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct SellOrderV1 {
+    pub seller: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub price_per_token: u64,
+    pub created_at: i64,
+    pub is_active: bool,
+}
+
+impl SellOrderV1 {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+// V1 with the marketplace the order was listed in, before orders had a nonce
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct SellOrderV2 {
+    pub marketplace: Pubkey,
+    pub seller: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub price_per_token: u64,
+    pub created_at: i64,
+    pub is_active: bool,
+}
+
+impl SellOrderV2 {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+// Per-seller state in a marketplace, created with the seller's first order
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct SellerState {
+    pub marketplace: Pubkey,
+    pub seller: Pubkey,
+    pub order_count: u64, // Orders created so far, and so the nonce of the next one
+}
+
+impl SellerState {
+    pub const LEN: usize = 32 + 32 + 8;
+}
+
+// Seed prefixes for sell orders and seller state
+pub const ORDER_SEED: &[u8] = b"sell_order";
+pub const SELLER_SEED: &[u8] = b"seller";
+
+// A seller's state in a marketplace: ["seller", marketplace, seller]
+pub fn find_seller_state_address(program_id: &Pubkey, marketplace: &Pubkey, seller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SELLER_SEED, marketplace.as_ref(), seller.as_ref()], program_id)
+}
+
+// A sell order: ["sell_order", marketplace, seller, mint, nonce (u64 LE)]. A seller's orders for a
+// mint are found by deriving nonces 0 up to their state's order_count.
+pub fn find_order_address(
+    program_id: &Pubkey,
+    marketplace: &Pubkey,
+    seller: &Pubkey,
+    mint: &Pubkey,
+    nonce: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ORDER_SEED, marketplace.as_ref(), seller.as_ref(), mint.as_ref(), &nonce.to_le_bytes()],
+        program_id,
+    )
+}
+
+// Seed prefix for the token account that holds an order's listed tokens in escrow
+pub const VAULT_SEED: &[u8] = b"vault";
+
+// An order's escrow token account: ["vault", order]. The vault is its own token authority, so
+// only the program can move tokens out of it.
+pub fn find_vault_address(program_id: &Pubkey, order: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, order.as_ref()], program_id)
+}
@@ -0,0 +1,48 @@
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+};
+use spl_token::state::Account as TokenAccount;
+
+pub fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> Result<(), ProgramError> {
+    if !rent.is_exempt(account_info.lamports(), account_info.data_len()) {
+        Err(ProgramError::AccountNotRentExempt)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> Result<(), ProgramError> {
+    if account.owner != owner {
+        Err(ProgramError::IncorrectProgramId)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn assert_program_id(account: &AccountInfo, program_id: &Pubkey) -> Result<(), ProgramError> {
+    if account.key != program_id {
+        Err(ProgramError::IncorrectProgramId)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn assert_signer(account: &AccountInfo) -> Result<(), ProgramError> {
+    if !account.is_signer {
+        Err(ProgramError::MissingRequiredSignature)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn get_token_account(account_info: &AccountInfo) -> Result<TokenAccount, ProgramError> {
+    TokenAccount::unpack(&account_info.try_borrow_data()?)
+}
+
+pub fn assert_token_account_owner(token_account: &TokenAccount, expected_owner: &Pubkey) -> Result<(), ProgramError> {
+    if token_account.owner != *expected_owner {
+        Err(ProgramError::InvalidAccountData)
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,1164 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+use solana_token_marketplace::{
+    error::MarketplaceError,
+    instruction::{buy_tokens, cancel_order, create_sell_order, initialize_marketplace, sweep_orders, update_price},
+    process_instruction,
+    state::{find_order_address, find_seller_state_address, find_vault_address, Marketplace, SellOrder, SellerState},
+};
+
+const FEE_PERCENTAGE: u16 = 250;
+const SELLER_BALANCE: u64 = 1_000;
+const ORDER_AMOUNT: u64 = 400;
+const PRICE: u64 = 1_000;
+
+async fn start() -> (ProgramTestContext, Pubkey) {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new("solana_token_marketplace", program_id, processor!(process_instruction));
+    (program_test.start_with_context().await, program_id)
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction =
+        Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(transaction).await
+}
+
+fn assert_marketplace_error(result: Result<(), BanksClientError>, expected: MarketplaceError) {
+    assert_instruction_error(result, InstructionError::Custom(expected as u32));
+}
+
+fn assert_instruction_error(result: Result<(), BanksClientError>, expected: InstructionError) {
+    match result.expect_err("transaction should fail").unwrap() {
+        TransactionError::InstructionError(_, error) => assert_eq!(error, expected),
+        other => panic!("expected {:?}, got {:?}", expected, other),
+    }
+}
+
+async fn fund(context: &mut ProgramTestContext, to: &Pubkey, lamports: u64) {
+    let instruction = system_instruction::transfer(&context.payer.pubkey(), to, lamports);
+    process(context, &[instruction], &[]).await.unwrap();
+}
+
+async fn lamports(context: &mut ProgramTestContext, address: &Pubkey) -> u64 {
+    context.banks_client.get_balance(*address).await.unwrap()
+}
+
+async fn read_state<T: BorshDeserialize>(context: &mut ProgramTestContext, address: &Pubkey) -> T {
+    let account = context.banks_client.get_account(*address).await.unwrap().unwrap();
+    T::try_from_slice(&account.data).unwrap()
+}
+
+// An account holding `state` that `owner` controls, standing in for one an attacker set up
+fn forge<T: BorshSerialize>(context: &mut ProgramTestContext, state: &T, owner: &Pubkey) -> Pubkey {
+    let address = Pubkey::new_unique();
+    forge_at(context, &address, state.try_to_vec().unwrap(), owner);
+    address
+}
+
+fn forge_at(context: &mut ProgramTestContext, address: &Pubkey, data: Vec<u8>, owner: &Pubkey) {
+    let account = Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    };
+    context.set_account(address, &account.into());
+}
+
+async fn account_exists(context: &mut ProgramTestContext, address: &Pubkey) -> bool {
+    context.banks_client.get_account(*address).await.unwrap().is_some()
+}
+
+async fn create_mint(context: &mut ProgramTestContext) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &context.payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(&spl_token::id(), &mint.pubkey(), &context.payer.pubkey(), None, 0)
+            .unwrap(),
+    ];
+    process(context, &instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+async fn create_token_account(context: &mut ProgramTestContext, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+    let account = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &context.payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(&spl_token::id(), &account.pubkey(), mint, owner).unwrap(),
+    ];
+    process(context, &instructions, &[&account]).await.unwrap();
+    account.pubkey()
+}
+
+async fn mint_to(context: &mut ProgramTestContext, mint: &Pubkey, account: &Pubkey, amount: u64) {
+    let instruction =
+        spl_token::instruction::mint_to(&spl_token::id(), mint, account, &context.payer.pubkey(), &[], amount)
+            .unwrap();
+    process(context, &[instruction], &[]).await.unwrap();
+}
+
+async fn token_balance(context: &mut ProgramTestContext, account: &Pubkey) -> u64 {
+    let account = context.banks_client.get_account(*account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+// A marketplace, a seller holding SELLER_BALANCE tokens and a buyer with an empty token account
+struct Market {
+    program_id: Pubkey,
+    admin: Keypair,
+    marketplace: Pubkey,
+    mint: Pubkey,
+    seller: Keypair,
+    seller_tokens: Pubkey,
+    buyer: Keypair,
+    buyer_tokens: Pubkey,
+}
+
+impl Market {
+    async fn new(context: &mut ProgramTestContext, program_id: Pubkey) -> Market {
+        let admin = Keypair::new();
+        let marketplace = Keypair::new();
+        let seller = Keypair::new();
+        let buyer = Keypair::new();
+        for account in [&admin, &seller, &buyer] {
+            fund(context, &account.pubkey(), 1_000_000_000).await;
+        }
+
+        let instruction = initialize_marketplace(&program_id, &admin.pubkey(), &marketplace.pubkey(), FEE_PERCENTAGE);
+        process(context, &[instruction], &[&admin, &marketplace]).await.unwrap();
+
+        let mint = create_mint(context).await;
+        let seller_tokens = create_token_account(context, &mint, &seller.pubkey()).await;
+        mint_to(context, &mint, &seller_tokens, SELLER_BALANCE).await;
+        let buyer_tokens = create_token_account(context, &mint, &buyer.pubkey()).await;
+
+        Market {
+            program_id,
+            admin,
+            marketplace: marketplace.pubkey(),
+            mint,
+            seller,
+            seller_tokens,
+            buyer,
+            buyer_tokens,
+        }
+    }
+
+    fn create_order_instruction(&self, nonce: u64, amount: u64, price: u64) -> Instruction {
+        create_sell_order(
+            &self.program_id,
+            &self.seller.pubkey(),
+            &self.seller_tokens,
+            &self.marketplace,
+            &self.mint,
+            nonce,
+            amount,
+            price,
+        )
+    }
+
+    fn order_address(&self, nonce: u64) -> Pubkey {
+        find_order_address(&self.program_id, &self.marketplace, &self.seller.pubkey(), &self.mint, nonce).0
+    }
+
+    fn seller_state_address(&self) -> Pubkey {
+        find_seller_state_address(&self.program_id, &self.marketplace, &self.seller.pubkey()).0
+    }
+
+    async fn order_count(&self, context: &mut ProgramTestContext) -> u64 {
+        match context.banks_client.get_account(self.seller_state_address()).await.unwrap() {
+            Some(account) if account.owner == self.program_id => {
+                SellerState::try_from_slice(&account.data).unwrap().order_count
+            }
+            _ => 0,
+        }
+    }
+
+    async fn create_order(&self, context: &mut ProgramTestContext) -> Pubkey {
+        let nonce = self.order_count(context).await;
+        let instruction = self.create_order_instruction(nonce, ORDER_AMOUNT, PRICE);
+        process(context, &[instruction], &[&self.seller]).await.unwrap();
+        self.order_address(nonce)
+    }
+
+    fn buy_instruction(&self, order: &Pubkey, amount: u64) -> Instruction {
+        buy_tokens(
+            &self.program_id,
+            &self.buyer.pubkey(),
+            &self.buyer_tokens,
+            &self.seller.pubkey(),
+            order,
+            &self.marketplace,
+            &self.admin.pubkey(),
+            &self.mint,
+            amount,
+        )
+    }
+
+    async fn buy(&self, context: &mut ProgramTestContext, order: &Pubkey, amount: u64) -> Result<(), BanksClientError> {
+        let instruction = self.buy_instruction(order, amount);
+        process(context, &[instruction], &[&self.buyer]).await
+    }
+
+    // An inactive order in the current layout. No instruction leaves one behind any more, as
+    // fills and cancels close the order, but the is_active checks still guard against it.
+    fn inactive_order(&self, context: &mut ProgramTestContext) -> Pubkey {
+        let order = SellOrder {
+            marketplace: self.marketplace,
+            seller: self.seller.pubkey(),
+            token_mint: self.mint,
+            nonce: 0,
+            amount: 0,
+            price_per_token: PRICE,
+            created_at: 0,
+            is_active: false,
+        };
+        forge(context, &order, &self.program_id)
+    }
+
+    // An order as an earlier version of the program wrote it, byte for byte. V1 orders have no
+    // marketplace field (89 bytes), V2 orders have one but no nonce (121 bytes). Both versions
+    // left orders open once they were filled or cancelled.
+    fn legacy_order(&self, context: &mut ProgramTestContext, with_marketplace: bool, is_active: bool) -> Pubkey {
+        let mut data = Vec::new();
+        if with_marketplace {
+            data.extend_from_slice(self.marketplace.as_ref());
+        }
+        data.extend_from_slice(self.seller.pubkey().as_ref());
+        data.extend_from_slice(self.mint.as_ref());
+        data.extend_from_slice(&0u64.to_le_bytes()); // amount
+        data.extend_from_slice(&PRICE.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // created_at
+        data.push(is_active as u8);
+        assert_eq!(data.len(), if with_marketplace { 121 } else { 89 });
+
+        let address = Pubkey::new_unique();
+        forge_at(context, &address, data, &self.program_id);
+        address
+    }
+
+    // The emptied vault a V1 order listed after escrow, or a V2 order, left behind
+    fn emptied_vault(&self, context: &mut ProgramTestContext, order: &Pubkey) -> Pubkey {
+        let (vault, _) = find_vault_address(&self.program_id, order);
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        let vault_account = spl_token::state::Account {
+            mint: self.mint,
+            owner: vault,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        spl_token::state::Account::pack(vault_account, &mut data).unwrap();
+        forge_at(context, &vault, data, &spl_token::id());
+        vault
+    }
+
+    async fn cancel(&self, context: &mut ProgramTestContext, order: &Pubkey) -> Result<(), BanksClientError> {
+        let instruction = cancel_order(&self.program_id, &self.seller.pubkey(), &self.seller_tokens, order);
+        process(context, &[instruction], &[&self.seller]).await
+    }
+}
+
+#[tokio::test]
+async fn initialize_marketplace_stores_admin_and_fee() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    let marketplace: Marketplace = read_state(&mut context, &market.marketplace).await;
+    assert_eq!(marketplace.admin, market.admin.pubkey());
+    assert_eq!(marketplace.fee_percentage, FEE_PERCENTAGE);
+    assert_eq!(marketplace.total_volume, 0);
+    assert_eq!(marketplace.total_fees_collected, 0);
+    assert!(marketplace.is_initialized);
+
+    let account = context.banks_client.get_account(market.marketplace).await.unwrap().unwrap();
+    assert_eq!(account.owner, program_id);
+    assert_eq!(account.data.len(), Marketplace::LEN);
+}
+
+#[tokio::test]
+async fn initialize_marketplace_rejects_fee_over_ten_percent() {
+    let (mut context, program_id) = start().await;
+    let admin = Keypair::new();
+    let marketplace = Keypair::new();
+    fund(&mut context, &admin.pubkey(), 1_000_000_000).await;
+
+    let instruction = initialize_marketplace(&program_id, &admin.pubkey(), &marketplace.pubkey(), 1_001);
+    let result = process(&mut context, &[instruction], &[&admin, &marketplace]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidFeePercentage);
+}
+
+#[tokio::test]
+async fn initialize_marketplace_requires_admin_signature() {
+    let (mut context, program_id) = start().await;
+    let admin = Keypair::new();
+    let marketplace = Keypair::new();
+
+    let mut instruction = initialize_marketplace(&program_id, &admin.pubkey(), &marketplace.pubkey(), FEE_PERCENTAGE);
+    instruction.accounts[0].is_signer = false;
+    let result = process(&mut context, &[instruction], &[&marketplace]).await;
+    assert_marketplace_error(result, MarketplaceError::NotAuthorized);
+}
+
+#[tokio::test]
+async fn initialize_marketplace_twice_fails() {
+    let (mut context, program_id) = start().await;
+    let admin = Keypair::new();
+    let marketplace = Keypair::new();
+    fund(&mut context, &admin.pubkey(), 1_000_000_000).await;
+
+    let instruction = initialize_marketplace(&program_id, &admin.pubkey(), &marketplace.pubkey(), FEE_PERCENTAGE);
+    process(&mut context, &[instruction], &[&admin, &marketplace]).await.unwrap();
+
+    // A different fee keeps the second transaction from being deduplicated
+    let instruction = initialize_marketplace(&program_id, &admin.pubkey(), &marketplace.pubkey(), 0);
+    let result = process(&mut context, &[instruction], &[&admin, &marketplace]).await;
+    assert_marketplace_error(result, MarketplaceError::AlreadyInitialized);
+    let marketplace: Marketplace = read_state(&mut context, &marketplace.pubkey()).await;
+    assert_eq!(marketplace.fee_percentage, FEE_PERCENTAGE);
+}
+
+#[tokio::test]
+async fn create_sell_order_records_the_listing() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let sell_order: SellOrder = read_state(&mut context, &order).await;
+    assert_eq!(sell_order.seller, market.seller.pubkey());
+    assert_eq!(sell_order.token_mint, market.mint);
+    assert_eq!(sell_order.amount, ORDER_AMOUNT);
+    assert_eq!(sell_order.price_per_token, PRICE);
+    assert!(sell_order.is_active);
+}
+
+#[tokio::test]
+async fn create_sell_order_derives_addresses_from_the_seller_counter() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    assert!(!account_exists(&mut context, &market.seller_state_address()).await);
+
+    let first = market.create_order(&mut context).await;
+    let second = market.create_order(&mut context).await;
+    assert_eq!(first, market.order_address(0));
+    assert_eq!(second, market.order_address(1));
+
+    let sell_order: SellOrder = read_state(&mut context, &second).await;
+    assert_eq!(sell_order.nonce, 1);
+
+    let seller_state: SellerState = read_state(&mut context, &market.seller_state_address()).await;
+    assert_eq!(seller_state.marketplace, market.marketplace);
+    assert_eq!(seller_state.seller, market.seller.pubkey());
+    assert_eq!(seller_state.order_count, 2);
+
+    // Nonces are never reused, even once an order is closed
+    market.cancel(&mut context, &second).await.unwrap();
+    let third = market.create_order(&mut context).await;
+    assert_eq!(third, market.order_address(2));
+    assert_eq!(market.order_count(&mut context).await, 3);
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_stale_nonce() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    market.create_order(&mut context).await;
+
+    let instruction = market.create_order_instruction(0, ORDER_AMOUNT / 2, PRICE);
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidOrderAddress);
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_another_sellers_state() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    let mut instruction = market.create_order_instruction(0, ORDER_AMOUNT, PRICE);
+    instruction.accounts[5].pubkey = find_seller_state_address(&program_id, &market.marketplace, &market.buyer.pubkey()).0;
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidSellerState);
+}
+
+#[tokio::test]
+async fn create_sell_order_succeeds_when_its_addresses_are_prefunded() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    // Every address the order needs can be computed and funded before the seller lists. The
+    // smallest transfer the runtime accepts to a new address is the rent for an empty account.
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let order = market.order_address(0);
+    let (vault, _) = find_vault_address(&program_id, &order);
+    for address in [market.seller_state_address(), order, vault] {
+        fund(&mut context, &address, rent.minimum_balance(0)).await;
+    }
+
+    assert_eq!(market.create_order(&mut context).await, order);
+    assert_eq!(token_balance(&mut context, &vault).await, ORDER_AMOUNT);
+    assert_eq!(market.order_count(&mut context).await, 1);
+
+    let account = context.banks_client.get_account(order).await.unwrap().unwrap();
+    assert_eq!(account.owner, program_id);
+    assert_eq!(account.lamports, rent.minimum_balance(SellOrder::LEN));
+}
+
+#[tokio::test]
+async fn create_sell_order_escrows_the_listed_tokens() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let (vault, _) = find_vault_address(&program_id, &order);
+    assert_eq!(token_balance(&mut context, &vault).await, ORDER_AMOUNT);
+    assert_eq!(token_balance(&mut context, &market.seller_tokens).await, SELLER_BALANCE - ORDER_AMOUNT);
+
+    let account = context.banks_client.get_account(vault).await.unwrap().unwrap();
+    let vault_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    assert_eq!(vault_account.mint, market.mint);
+    assert_eq!(vault_account.owner, vault);
+
+    // The listed tokens are out of the seller's reach until the order is cancelled
+    let other = create_token_account(&mut context, &market.mint, &market.seller.pubkey()).await;
+    let instruction = spl_token::instruction::transfer(
+        &spl_token::id(),
+        &market.seller_tokens,
+        &other,
+        &market.seller.pubkey(),
+        &[],
+        SELLER_BALANCE - ORDER_AMOUNT + 1,
+    )
+    .unwrap();
+    assert!(process(&mut context, &[instruction], &[&market.seller]).await.is_err());
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_wrong_vault() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    let mut instruction = market.create_order_instruction(0, ORDER_AMOUNT, PRICE);
+    instruction.accounts[3].pubkey = find_vault_address(&program_id, &Pubkey::new_unique()).0;
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidTokenAccount);
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_zero_amount_or_price() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    for (amount, price) in [(0, PRICE), (ORDER_AMOUNT, 0)] {
+        let instruction = market.create_order_instruction(0, amount, price);
+        let result = process(&mut context, &[instruction], &[&market.seller]).await;
+        assert_marketplace_error(result, MarketplaceError::InvalidAmount);
+    }
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_more_than_the_seller_holds() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    let instruction = market.create_order_instruction(0, SELLER_BALANCE + 1, PRICE);
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_marketplace_error(result, MarketplaceError::InsufficientFunds);
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_mismatched_mint() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let other_mint = create_mint(&mut context).await;
+
+    let instruction = create_sell_order(
+        &program_id,
+        &market.seller.pubkey(),
+        &market.seller_tokens,
+        &market.marketplace,
+        &other_mint,
+        0,
+        ORDER_AMOUNT,
+        PRICE,
+    );
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidMint);
+}
+
+#[tokio::test]
+async fn create_sell_order_requires_seller_signature() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    let mut instruction = market.create_order_instruction(0, ORDER_AMOUNT, PRICE);
+    instruction.accounts[0].is_signer = false;
+    let result = process(&mut context, &[instruction], &[]).await;
+    assert_marketplace_error(result, MarketplaceError::NotAuthorized);
+}
+
+#[tokio::test]
+async fn buy_tokens_pays_seller_and_fee() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+    let seller_before = lamports(&mut context, &market.seller.pubkey()).await;
+    let admin_before = lamports(&mut context, &market.admin.pubkey()).await;
+    let buyer_before = lamports(&mut context, &market.buyer.pubkey()).await;
+
+    market.buy(&mut context, &order, 100).await.unwrap();
+
+    let total = 100 * PRICE;
+    let fee = total * FEE_PERCENTAGE as u64 / 10_000;
+    assert_eq!(lamports(&mut context, &market.seller.pubkey()).await, seller_before + total - fee);
+    assert_eq!(lamports(&mut context, &market.admin.pubkey()).await, admin_before + fee);
+    assert_eq!(lamports(&mut context, &market.buyer.pubkey()).await, buyer_before - total);
+    assert_eq!(token_balance(&mut context, &market.buyer_tokens).await, 100);
+    let (vault, _) = find_vault_address(&program_id, &order);
+    assert_eq!(token_balance(&mut context, &vault).await, ORDER_AMOUNT - 100);
+    assert_eq!(token_balance(&mut context, &market.seller_tokens).await, SELLER_BALANCE - ORDER_AMOUNT);
+
+    let sell_order: SellOrder = read_state(&mut context, &order).await;
+    assert_eq!(sell_order.amount, ORDER_AMOUNT - 100);
+    assert!(sell_order.is_active);
+
+    let marketplace: Marketplace = read_state(&mut context, &market.marketplace).await;
+    assert_eq!(marketplace.total_volume, total);
+    assert_eq!(marketplace.total_fees_collected, fee);
+}
+
+#[tokio::test]
+async fn buy_tokens_filling_the_order_closes_it() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+    let (vault, _) = find_vault_address(&program_id, &order);
+    let rent = lamports(&mut context, &order).await + lamports(&mut context, &vault).await;
+    let seller_before = lamports(&mut context, &market.seller.pubkey()).await;
+
+    market.buy(&mut context, &order, ORDER_AMOUNT).await.unwrap();
+
+    assert!(!account_exists(&mut context, &order).await);
+    assert!(!account_exists(&mut context, &vault).await);
+    let total = ORDER_AMOUNT * PRICE;
+    let fee = total * FEE_PERCENTAGE as u64 / 10_000;
+    assert_eq!(lamports(&mut context, &market.seller.pubkey()).await, seller_before + total - fee + rent);
+    assert_eq!(token_balance(&mut context, &market.buyer_tokens).await, ORDER_AMOUNT);
+
+    let result = market.buy(&mut context, &order, 1).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_inactive_order() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.inactive_order(&mut context);
+
+    let result = market.buy(&mut context, &order, 1).await;
+    assert_marketplace_error(result, MarketplaceError::OrderNotActive);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_more_than_the_order_holds() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let result = market.buy(&mut context, &order, ORDER_AMOUNT + 1).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidAmount);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_buyer_without_enough_lamports() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let instruction = market.create_order_instruction(0, ORDER_AMOUNT, 10_000_000);
+    process(&mut context, &[instruction], &[&market.seller]).await.unwrap();
+
+    let result = market.buy(&mut context, &market.order_address(0), ORDER_AMOUNT).await;
+    assert_marketplace_error(result, MarketplaceError::InsufficientFunds);
+}
+
+#[tokio::test]
+async fn buy_tokens_requires_buyer_signature() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let mut instruction = market.buy_instruction(&order, 100);
+    instruction.accounts[0].is_signer = false;
+    let result = process(&mut context, &[instruction], &[]).await;
+    assert_marketplace_error(result, MarketplaceError::NotAuthorized);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_another_orders_vault() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+    let other_order = market.create_order(&mut context).await;
+
+    let mut instruction = market.buy_instruction(&order, 100);
+    instruction.accounts[3].pubkey = find_vault_address(&program_id, &other_order).0;
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidTokenAccount);
+}
+
+#[tokio::test]
+async fn cancel_order_returns_the_remaining_tokens() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+    market.buy(&mut context, &order, 100).await.unwrap();
+    let (vault, _) = find_vault_address(&program_id, &order);
+    let rent = lamports(&mut context, &order).await + lamports(&mut context, &vault).await;
+    let seller_before = lamports(&mut context, &market.seller.pubkey()).await;
+
+    market.cancel(&mut context, &order).await.unwrap();
+
+    assert_eq!(token_balance(&mut context, &market.seller_tokens).await, SELLER_BALANCE - 100);
+    assert!(!account_exists(&mut context, &order).await);
+    assert!(!account_exists(&mut context, &vault).await);
+    assert_eq!(lamports(&mut context, &market.seller.pubkey()).await, seller_before + rent);
+
+    let result = market.buy(&mut context, &order, 1).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn cancel_order_rejects_closed_order() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+    market.buy(&mut context, &order, ORDER_AMOUNT).await.unwrap();
+
+    let result = market.cancel(&mut context, &order).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn cancelled_order_cannot_be_reused_in_the_same_transaction() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+    let rent = context.banks_client.get_rent().await.unwrap().minimum_balance(SellOrder::LEN);
+    let cancel = cancel_order(&program_id, &market.seller.pubkey(), &market.seller_tokens, &order);
+    let refund = system_instruction::transfer(&context.payer.pubkey(), &order, rent);
+
+    // Refunding the closed order keeps it alive past the transaction, but not as an order
+    let result = process(&mut context, &[cancel.clone(), refund.clone(), cancel.clone()], &[&market.seller]).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+
+    process(&mut context, &[cancel, refund], &[&market.seller]).await.unwrap();
+    let account = context.banks_client.get_account(order).await.unwrap().unwrap();
+    assert_eq!(account.owner, solana_sdk::system_program::id());
+    assert!(account.data.is_empty());
+}
+
+#[tokio::test]
+async fn cancel_order_rejects_inactive_order() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.inactive_order(&mut context);
+
+    let result = market.cancel(&mut context, &order).await;
+    assert_marketplace_error(result, MarketplaceError::OrderNotActive);
+}
+
+#[tokio::test]
+async fn cancel_order_rejects_other_signer() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let instruction = cancel_order(&program_id, &market.buyer.pubkey(), &market.buyer_tokens, &order);
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::NotAuthorized);
+}
+
+#[tokio::test]
+async fn cancel_order_requires_seller_signature() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let mut instruction = cancel_order(&program_id, &market.seller.pubkey(), &market.seller_tokens, &order);
+    instruction.accounts[0].is_signer = false;
+    let result = process(&mut context, &[instruction], &[]).await;
+    assert_marketplace_error(result, MarketplaceError::NotAuthorized);
+}
+
+#[tokio::test]
+async fn update_price_changes_the_price() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let instruction = update_price(&program_id, &market.seller.pubkey(), &order, PRICE * 2);
+    process(&mut context, &[instruction], &[&market.seller]).await.unwrap();
+
+    let sell_order: SellOrder = read_state(&mut context, &order).await;
+    assert_eq!(sell_order.price_per_token, PRICE * 2);
+}
+
+#[tokio::test]
+async fn update_price_rejects_zero() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let instruction = update_price(&program_id, &market.seller.pubkey(), &order, 0);
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidAmount);
+}
+
+#[tokio::test]
+async fn update_price_rejects_other_signer() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let instruction = update_price(&program_id, &market.buyer.pubkey(), &order, PRICE * 2);
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::NotAuthorized);
+}
+
+#[tokio::test]
+async fn update_price_rejects_inactive_order() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.inactive_order(&mut context);
+
+    let instruction = update_price(&program_id, &market.seller.pubkey(), &order, PRICE * 2);
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_marketplace_error(result, MarketplaceError::OrderNotActive);
+}
+
+#[tokio::test]
+async fn update_price_requires_seller_signature() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let mut instruction = update_price(&program_id, &market.seller.pubkey(), &order, PRICE * 2);
+    instruction.accounts[0].is_signer = false;
+    let result = process(&mut context, &[instruction], &[]).await;
+    assert_marketplace_error(result, MarketplaceError::NotAuthorized);
+}
+
+#[tokio::test]
+async fn unknown_instruction_is_rejected() {
+    let (mut context, program_id) = start().await;
+    let instruction = Instruction::new_with_bytes(program_id, &[42], vec![]);
+    let result = process(&mut context, &[instruction], &[]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidInstruction);
+}
+
+#[tokio::test]
+async fn sweep_orders_closes_inactive_orders_and_refunds_sellers() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    // V1 orders listed before escrow have no vault; later V1 orders and all V2 orders do
+    let v1_without_vault = market.legacy_order(&mut context, false, false);
+    let v1_with_vault = market.legacy_order(&mut context, false, false);
+    let v2 = market.legacy_order(&mut context, true, false);
+    let v1_vault = market.emptied_vault(&mut context, &v1_with_vault);
+    let v2_vault = market.emptied_vault(&mut context, &v2);
+    let accounts = [v1_without_vault, v1_with_vault, v2, v1_vault, v2_vault];
+
+    let mut refund = 0;
+    for address in accounts {
+        refund += lamports(&mut context, &address).await;
+    }
+    let seller_before = lamports(&mut context, &market.seller.pubkey()).await;
+
+    let seller = market.seller.pubkey();
+    let instruction = sweep_orders(
+        &program_id,
+        &market.admin.pubkey(),
+        &market.marketplace,
+        &[(v1_without_vault, seller), (v1_with_vault, seller), (v2, seller)],
+    );
+    process(&mut context, &[instruction], &[&market.admin]).await.unwrap();
+
+    for address in accounts {
+        assert!(!account_exists(&mut context, &address).await);
+    }
+    assert_eq!(lamports(&mut context, &seller).await, seller_before + refund);
+}
+
+#[tokio::test]
+async fn sweep_orders_rejects_wrong_or_missing_vault() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.legacy_order(&mut context, true, false);
+    let other_order = market.legacy_order(&mut context, true, false);
+    let other_vault = market.emptied_vault(&mut context, &other_order);
+    let system_account = Pubkey::new_unique();
+    fund(&mut context, &system_account, 1_000_000_000).await;
+    let orders = [(order, market.seller.pubkey())];
+
+    // Another order's vault, or an account that is not a vault at all, in the vault slot
+    for vault in [other_vault, system_account] {
+        let mut instruction = sweep_orders(&program_id, &market.admin.pubkey(), &market.marketplace, &orders);
+        instruction.accounts[4].pubkey = vault;
+        let result = process(&mut context, &[instruction], &[&market.admin]).await;
+        assert_marketplace_error(result, MarketplaceError::InvalidTokenAccount);
+    }
+
+    // A V2 order was always escrowed, so its vault cannot be missing
+    let instruction = sweep_orders(&program_id, &market.admin.pubkey(), &market.marketplace, &orders);
+    let result = process(&mut context, &[instruction], &[&market.admin]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidTokenAccount);
+    assert!(account_exists(&mut context, &order).await);
+}
+
+#[tokio::test]
+async fn sweep_orders_requires_the_admin() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.legacy_order(&mut context, true, false);
+    let orders = [(order, market.seller.pubkey())];
+
+    let instruction = sweep_orders(&program_id, &market.buyer.pubkey(), &market.marketplace, &orders);
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidAdmin);
+
+    let mut instruction = sweep_orders(&program_id, &market.admin.pubkey(), &market.marketplace, &orders);
+    instruction.accounts[0].is_signer = false;
+    let result = process(&mut context, &[instruction], &[]).await;
+    assert_marketplace_error(result, MarketplaceError::NotAuthorized);
+}
+
+#[tokio::test]
+async fn sweep_orders_rejects_active_order() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let current = market.create_order(&mut context).await;
+    let v1 = market.legacy_order(&mut context, false, true);
+    let v2 = market.legacy_order(&mut context, true, true);
+
+    for order in [current, v1, v2] {
+        let orders = [(order, market.seller.pubkey())];
+        let instruction = sweep_orders(&program_id, &market.admin.pubkey(), &market.marketplace, &orders);
+        let result = process(&mut context, &[instruction], &[&market.admin]).await;
+        assert_marketplace_error(result, MarketplaceError::OrderStillActive);
+    }
+}
+
+#[tokio::test]
+async fn sweep_orders_rejects_unknown_layout() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = Pubkey::new_unique();
+    forge_at(&mut context, &order, vec![0; 100], &program_id);
+
+    let orders = [(order, market.seller.pubkey())];
+    let instruction = sweep_orders(&program_id, &market.admin.pubkey(), &market.marketplace, &orders);
+    let result = process(&mut context, &[instruction], &[&market.admin]).await;
+    assert_instruction_error(result, InstructionError::InvalidAccountData);
+}
+
+#[tokio::test]
+async fn sweep_orders_rejects_refund_to_another_account() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    for with_marketplace in [false, true] {
+        let order = market.legacy_order(&mut context, with_marketplace, false);
+        let orders = [(order, market.admin.pubkey())];
+        let instruction = sweep_orders(&program_id, &market.admin.pubkey(), &market.marketplace, &orders);
+        let result = process(&mut context, &[instruction], &[&market.admin]).await;
+        assert_marketplace_error(result, MarketplaceError::InvalidSeller);
+    }
+}
+
+#[tokio::test]
+async fn sweep_orders_rejects_order_from_another_marketplace() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let other = Market::new(&mut context, program_id).await;
+    let order = other.legacy_order(&mut context, true, false);
+
+    let orders = [(order, other.seller.pubkey())];
+    let instruction = sweep_orders(&program_id, &market.admin.pubkey(), &market.marketplace, &orders);
+    let result = process(&mut context, &[instruction], &[&market.admin]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidMarketplace);
+}
+
+#[tokio::test]
+async fn sweep_orders_lets_any_admin_sweep_v1_orders_to_their_seller() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.legacy_order(&mut context, false, false);
+    let refund = lamports(&mut context, &order).await;
+    let seller_before = lamports(&mut context, &market.seller.pubkey()).await;
+
+    // Anyone can become the admin of a marketplace of their own, but the rent still goes to
+    // the seller recorded in the order
+    let stranger = Market::new(&mut context, program_id).await;
+    let orders = [(order, stranger.admin.pubkey())];
+    let instruction = sweep_orders(&program_id, &stranger.admin.pubkey(), &stranger.marketplace, &orders);
+    let result = process(&mut context, &[instruction], &[&stranger.admin]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidSeller);
+
+    let orders = [(order, market.seller.pubkey())];
+    let instruction = sweep_orders(&program_id, &stranger.admin.pubkey(), &stranger.marketplace, &orders);
+    process(&mut context, &[instruction], &[&stranger.admin]).await.unwrap();
+    assert!(!account_exists(&mut context, &order).await);
+    assert_eq!(lamports(&mut context, &market.seller.pubkey()).await, seller_before + refund);
+}
+
+// Attacks on account validation
+
+fn forged_marketplace(admin: &Pubkey) -> Marketplace {
+    Marketplace {
+        admin: *admin,
+        fee_percentage: 0,
+        total_volume: 0,
+        total_fees_collected: 0,
+        is_initialized: true,
+    }
+}
+
+fn forged_order(market: &Market) -> SellOrder {
+    SellOrder {
+        marketplace: market.marketplace,
+        seller: market.buyer.pubkey(),
+        token_mint: market.mint,
+        nonce: 0,
+        amount: ORDER_AMOUNT,
+        price_per_token: 1,
+        created_at: 0,
+        is_active: true,
+    }
+}
+
+#[tokio::test]
+async fn initialize_marketplace_rejects_fake_system_program() {
+    let (mut context, program_id) = start().await;
+    let admin = Keypair::new();
+    let marketplace = Keypair::new();
+    fund(&mut context, &admin.pubkey(), 1_000_000_000).await;
+
+    let mut instruction = initialize_marketplace(&program_id, &admin.pubkey(), &marketplace.pubkey(), FEE_PERCENTAGE);
+    instruction.accounts[2].pubkey = Pubkey::new_unique();
+    let result = process(&mut context, &[instruction], &[&admin, &marketplace]).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_fake_programs() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+
+    // System program, then token program
+    for index in [7, 8] {
+        let mut instruction = market.create_order_instruction(0, ORDER_AMOUNT, PRICE);
+        instruction.accounts[index].pubkey = Pubkey::new_unique();
+        let result = process(&mut context, &[instruction], &[&market.seller]).await;
+        assert_instruction_error(result, InstructionError::IncorrectProgramId);
+    }
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_forged_marketplace() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let forged = forge(&mut context, &forged_marketplace(&market.seller.pubkey()), &Pubkey::new_unique());
+
+    let mut instruction = market.create_order_instruction(0, ORDER_AMOUNT, PRICE);
+    instruction.accounts[4].pubkey = forged;
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn create_sell_order_rejects_someone_elses_token_account() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    mint_to(&mut context, &market.mint, &market.buyer_tokens, SELLER_BALANCE).await;
+
+    let mut instruction = market.create_order_instruction(0, ORDER_AMOUNT, PRICE);
+    instruction.accounts[1].pubkey = market.buyer_tokens;
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_instruction_error(result, InstructionError::InvalidAccountData);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_payment_to_another_seller() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let mut instruction = market.buy_instruction(&order, 100);
+    instruction.accounts[2].pubkey = market.buyer.pubkey();
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidSeller);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_fee_to_another_admin() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let mut instruction = market.buy_instruction(&order, 100);
+    instruction.accounts[6].pubkey = market.buyer.pubkey();
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidAdmin);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_buyers_own_zero_fee_marketplace() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let own_marketplace = Keypair::new();
+    let instruction = initialize_marketplace(&program_id, &market.buyer.pubkey(), &own_marketplace.pubkey(), 0);
+    process(&mut context, &[instruction], &[&market.buyer, &own_marketplace]).await.unwrap();
+
+    let mut instruction = market.buy_instruction(&order, 100);
+    instruction.accounts[5].pubkey = own_marketplace.pubkey();
+    instruction.accounts[6].pubkey = market.buyer.pubkey();
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidMarketplace);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_forged_marketplace() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+    let forged = forge(&mut context, &forged_marketplace(&market.buyer.pubkey()), &Pubkey::new_unique());
+
+    let mut instruction = market.buy_instruction(&order, 100);
+    instruction.accounts[5].pubkey = forged;
+    instruction.accounts[6].pubkey = market.buyer.pubkey();
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_forged_order() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    market.create_order(&mut context).await;
+    let forged = forge(&mut context, &forged_order(&market), &Pubkey::new_unique());
+
+    let mut instruction = market.buy_instruction(&forged, 100);
+    instruction.accounts[2].pubkey = market.buyer.pubkey();
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_token_account_for_another_mint() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+    let other_mint = create_mint(&mut context).await;
+    let other_tokens = create_token_account(&mut context, &other_mint, &market.buyer.pubkey()).await;
+
+    let mut instruction = market.buy_instruction(&order, 100);
+    instruction.accounts[1].pubkey = other_tokens;
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidMint);
+
+    let mut instruction = market.buy_instruction(&order, 100);
+    instruction.accounts[7].pubkey = other_mint;
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_marketplace_error(result, MarketplaceError::InvalidMint);
+}
+
+#[tokio::test]
+async fn buy_tokens_rejects_fake_programs() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    // System, token and associated token programs
+    for index in [8, 9, 10] {
+        let mut instruction = market.buy_instruction(&order, 100);
+        instruction.accounts[index].pubkey = Pubkey::new_unique();
+        let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+        assert_instruction_error(result, InstructionError::IncorrectProgramId);
+    }
+}
+
+#[tokio::test]
+async fn cancel_order_rejects_refund_to_another_account() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let instruction = cancel_order(&program_id, &market.seller.pubkey(), &market.buyer_tokens, &order);
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_instruction_error(result, InstructionError::InvalidAccountData);
+}
+
+#[tokio::test]
+async fn cancel_order_rejects_fake_token_program() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let order = market.create_order(&mut context).await;
+
+    let mut instruction = cancel_order(&program_id, &market.seller.pubkey(), &market.seller_tokens, &order);
+    instruction.accounts[4].pubkey = Pubkey::new_unique();
+    let result = process(&mut context, &[instruction], &[&market.seller]).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn cancel_order_rejects_forged_order() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let forged = forge(&mut context, &forged_order(&market), &Pubkey::new_unique());
+
+    let instruction = cancel_order(&program_id, &market.buyer.pubkey(), &market.buyer_tokens, &forged);
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
+
+#[tokio::test]
+async fn update_price_rejects_forged_order() {
+    let (mut context, program_id) = start().await;
+    let market = Market::new(&mut context, program_id).await;
+    let forged = forge(&mut context, &forged_order(&market), &Pubkey::new_unique());
+
+    let instruction = update_price(&program_id, &market.buyer.pubkey(), &forged, 1);
+    let result = process(&mut context, &[instruction], &[&market.buyer]).await;
+    assert_instruction_error(result, InstructionError::IncorrectProgramId);
+}
@@ -0,0 +1,98 @@
+use crate::KeypairError;
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+use solana_sdk::signature::{keypair_from_seed, Keypair};
+
+// Generate a new English BIP39 seed phrase with 12 or 24 words
+pub fn generate_mnemonic(word_count: usize) -> Result<String, KeypairError> {
+    if word_count != 12 && word_count != 24 {
+        return Err(KeypairError::UnsupportedWordCount(word_count));
+    }
+
+    let mnemonic_type =
+        MnemonicType::for_word_count(word_count).map_err(|_| KeypairError::UnsupportedWordCount(word_count))?;
+    let mnemonic = Mnemonic::new(mnemonic_type, Language::English);
+    Ok(mnemonic.into_phrase())
+}
+
+// Check that a seed phrase uses the English wordlist and has a valid checksum
+pub fn validate_mnemonic(phrase: &str) -> Result<(), KeypairError> {
+    Mnemonic::validate(phrase, Language::English).map_err(|e| KeypairError::InvalidMnemonic(e.to_string()))
+}
+
+// Derive the 64-byte BIP39 seed from a phrase and optional passphrase
+pub fn seed_from_mnemonic(phrase: &str, passphrase: &str) -> Result<Vec<u8>, KeypairError> {
+    let mnemonic =
+        Mnemonic::from_phrase(phrase, Language::English).map_err(|e| KeypairError::InvalidMnemonic(e.to_string()))?;
+    let seed = Seed::new(&mnemonic, passphrase);
+    Ok(seed.as_bytes().to_vec())
+}
+
+// Recover a keypair from a seed phrase the same way `solana-keygen recover` does
+// when no derivation path is given: the first 32 bytes of the seed are the secret
+pub fn keypair_from_mnemonic(phrase: &str, passphrase: &str) -> Result<Keypair, KeypairError> {
+    let seed = seed_from_mnemonic(phrase, passphrase)?;
+    keypair_from_seed(&seed).map_err(|e| KeypairError::InvalidSecretKey(e.to_string()))
+}
+
+// Generate a new seed phrase together with the keypair it recovers to
+pub fn generate_keypair_with_mnemonic(
+    word_count: usize,
+    passphrase: &str,
+) -> Result<(String, Keypair), KeypairError> {
+    let phrase = generate_mnemonic(word_count)?;
+    let keypair = keypair_from_mnemonic(&phrase, passphrase)?;
+    Ok((phrase, keypair))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn seed_matches_bip39_test_vector() {
+        let seed = seed_from_mnemonic(PHRASE, "TREZOR").unwrap();
+        assert_eq!(
+            hex::encode(seed),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+    }
+
+    #[test]
+    fn keypair_matches_solana_keygen_recover() {
+        // The first 32 bytes of the BIP39 seed, with and without a passphrase
+        for (passphrase, address) in [
+            ("", "EHqmfkN89RJ7Y33CXM6uCzhVeuywHoJXZZLszBHHZy7o"),
+            ("TREZOR", "6UChi37U4BGomEQR665JZNjTM2PiHcUmBCD3e7TD366v"),
+        ] {
+            let keypair = keypair_from_mnemonic(PHRASE, passphrase).unwrap();
+            assert_eq!(keypair.pubkey().to_string(), address);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_phrases() {
+        // Bad checksum, a word outside the English list, and too few words
+        for phrase in [
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon solana",
+            "abandon about",
+        ] {
+            assert!(validate_mnemonic(phrase).is_err(), "{}", phrase);
+            assert!(keypair_from_mnemonic(phrase, "").is_err(), "{}", phrase);
+        }
+    }
+
+    #[test]
+    fn generates_valid_phrases_that_recover_their_keypair() {
+        for word_count in [12, 24] {
+            let (phrase, keypair) = generate_keypair_with_mnemonic(word_count, "passphrase").unwrap();
+            assert_eq!(phrase.split(' ').count(), word_count);
+            validate_mnemonic(&phrase).unwrap();
+            assert_eq!(keypair_from_mnemonic(&phrase, "passphrase").unwrap().pubkey(), keypair.pubkey());
+        }
+        assert_eq!(generate_mnemonic(15), Err(KeypairError::UnsupportedWordCount(15)));
+    }
+}
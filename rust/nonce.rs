@@ -0,0 +1,362 @@
+// Durable nonce accounts for air-gapped signing.
+//
+// A transaction that starts with AdvanceNonceAccount and uses the nonce account's stored value as
+// its blockhash never expires, so it can be built online, carried to offline machines, signed by
+// each key holder with `partial_sign`, combined with `merge_signatures` and broadcast later.
+use crate::transaction::{
+    apply_options, build_transaction, build_transaction_with_signers, decode_transaction, encode_transaction,
+    TransactionEncoding, TransactionOptions,
+};
+use crate::{parse_pubkey, KeypairError};
+use base64::decode;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::nonce::state::{State, Versions};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+// The parts of an initialized nonce account needed to build a transaction against it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceInfo {
+    pub nonce_account: Pubkey,
+    pub authority: Pubkey,
+    // Used in place of a recent blockhash
+    pub nonce_value: Hash,
+    pub lamports_per_signature: u64,
+}
+
+// Rent-exempt balance for a nonce account at the default rent rate
+pub fn minimum_nonce_account_balance() -> u64 {
+    Rent::default().minimum_balance(State::size())
+}
+
+// Read a nonce account from its base64 account data, as returned by getAccountInfo
+pub fn decode_nonce_account(nonce_account: &str, data_base64: &str) -> Result<NonceInfo, KeypairError> {
+    let nonce_account = parse_pubkey(nonce_account)?;
+    let data = decode(data_base64.trim())?;
+    let versions: Versions =
+        bincode::deserialize(&data).map_err(|e| KeypairError::InvalidNonceAccount(e.to_string()))?;
+
+    match versions.state() {
+        State::Initialized(data) => Ok(NonceInfo {
+            nonce_account,
+            authority: data.authority,
+            nonce_value: *data.durable_nonce.as_hash(),
+            lamports_per_signature: data.get_lamports_per_signature(),
+        }),
+        State::Uninitialized => Err(KeypairError::InvalidNonceAccount(format!(
+            "{} is not initialized",
+            nonce_account
+        ))),
+    }
+}
+
+// Create and initialize a nonce account owned by `authority`, funded by `payer`
+pub fn build_create_nonce_account(
+    payer: &Keypair,
+    nonce_account: &Keypair,
+    authority: &str,
+    lamports: u64,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let authority = parse_pubkey(authority)?;
+    let instructions =
+        system_instruction::create_nonce_account(&payer.pubkey(), &nonce_account.pubkey(), &authority, lamports);
+    build_transaction_with_signers(payer, &[nonce_account], instructions, recent_blockhash, options)
+}
+
+// Move the nonce to a new value, invalidating anything signed against the old one
+pub fn build_advance_nonce(
+    authority: &Keypair,
+    nonce_account: &str,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let nonce_account = parse_pubkey(nonce_account)?;
+    let instruction = system_instruction::advance_nonce_account(&nonce_account, &authority.pubkey());
+    build_transaction(authority, vec![instruction], recent_blockhash, options)
+}
+
+// Hand the nonce account over to a new authority
+pub fn build_authorize_nonce(
+    authority: &Keypair,
+    nonce_account: &str,
+    new_authority: &str,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let nonce_account = parse_pubkey(nonce_account)?;
+    let new_authority = parse_pubkey(new_authority)?;
+    let instruction = system_instruction::authorize_nonce_account(&nonce_account, &authority.pubkey(), &new_authority);
+    build_transaction(authority, vec![instruction], recent_blockhash, options)
+}
+
+// Withdraw lamports from the nonce account; withdrawing everything closes it
+pub fn build_withdraw_nonce(
+    authority: &Keypair,
+    nonce_account: &str,
+    recipient: &str,
+    lamports: u64,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let nonce_account = parse_pubkey(nonce_account)?;
+    let recipient = parse_pubkey(recipient)?;
+    let instruction =
+        system_instruction::withdraw_nonce_account(&nonce_account, &authority.pubkey(), &recipient, lamports);
+    build_transaction(authority, vec![instruction], recent_blockhash, options)
+}
+
+// Build an unsigned transaction that uses the durable nonce instead of a recent blockhash.
+// The AdvanceNonceAccount instruction goes first, as the runtime requires.
+pub fn build_nonce_transaction(
+    fee_payer: &Pubkey,
+    instructions: Vec<Instruction>,
+    nonce: &NonceInfo,
+    options: &TransactionOptions,
+) -> Transaction {
+    let instructions = apply_options(fee_payer, instructions, options);
+    let mut message = Message::new_with_nonce(instructions, Some(fee_payer), &nonce.nonce_account, &nonce.authority);
+    message.recent_blockhash = nonce.nonce_value;
+    Transaction::new_unsigned(message)
+}
+
+// Signers whose signature is still missing
+pub fn missing_signers(transaction: &Transaction) -> Vec<Pubkey> {
+    let required = transaction.message.header.num_required_signatures as usize;
+    transaction
+        .message
+        .account_keys
+        .iter()
+        .take(required)
+        .zip(&transaction.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(pubkey, _)| *pubkey)
+        .collect()
+}
+
+// Add one signer's signature to a serialized transaction, keeping any already present
+pub fn partial_sign(
+    encoded: &str,
+    encoding: TransactionEncoding,
+    signer: &Keypair,
+) -> Result<String, KeypairError> {
+    let mut transaction = decode_transaction(encoded, encoding)?;
+    // Signing with the message's own blockhash keeps the other signatures in place
+    let blockhash = transaction.message.recent_blockhash;
+    transaction.try_partial_sign(&[signer], blockhash).map_err(|e| {
+        KeypairError::SigningFailed(format!("{} cannot sign this transaction: {}", signer.pubkey(), e))
+    })?;
+    encode_transaction(&transaction, encoding)
+}
+
+// Combine copies of the same transaction signed by different signers into one.
+// Every copy must carry the identical message, and a slot signed in two copies must agree.
+pub fn merge_signatures(encoded: &[&str], encoding: TransactionEncoding) -> Result<String, KeypairError> {
+    let mut transactions = encoded
+        .iter()
+        .map(|tx| decode_transaction(tx, encoding))
+        .collect::<Result<Vec<_>, _>>()?;
+    if transactions.is_empty() {
+        return Err(KeypairError::InvalidTransaction("no transactions to merge".to_string()));
+    }
+
+    let mut merged = transactions.remove(0);
+    let message_data = merged.message_data();
+    for transaction in transactions {
+        if transaction.message_data() != message_data {
+            return Err(KeypairError::InvalidTransaction(
+                "transactions to merge have different messages".to_string(),
+            ));
+        }
+
+        for (slot, signature) in merged.signatures.iter_mut().zip(transaction.signatures) {
+            if signature == Signature::default() {
+                continue;
+            }
+            if *slot != Signature::default() && *slot != signature {
+                return Err(KeypairError::InvalidTransaction(
+                    "transactions to merge carry conflicting signatures".to_string(),
+                ));
+            }
+            *slot = signature;
+        }
+    }
+
+    // Reject signatures that do not verify before anyone broadcasts the result
+    let results = merged.verify_with_results();
+    if let Some(position) = merged
+        .signatures
+        .iter()
+        .zip(&results)
+        .position(|(signature, ok)| *signature != Signature::default() && !ok)
+    {
+        return Err(KeypairError::InvalidTransaction(format!(
+            "signature for {} does not verify",
+            merged.message.account_keys[position]
+        )));
+    }
+
+    encode_transaction(&merged, encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::PriorityFee;
+    use solana_sdk::nonce::state::{Data, DurableNonce};
+    use solana_sdk::signature::keypair_from_seed;
+    use solana_sdk::system_instruction::SystemInstruction;
+    use solana_sdk::system_program;
+
+    const ENCODING: TransactionEncoding = TransactionEncoding::Base64;
+
+    struct Setup {
+        payer: Keypair,
+        authority: Keypair,
+        nonce: NonceInfo,
+    }
+
+    fn setup() -> Setup {
+        let authority = keypair_from_seed(&[2; 32]).unwrap();
+        let nonce = NonceInfo {
+            nonce_account: keypair_from_seed(&[3; 32]).unwrap().pubkey(),
+            authority: authority.pubkey(),
+            nonce_value: Hash::new_from_array([7; 32]),
+            lamports_per_signature: 5_000,
+        };
+        Setup { payer: keypair_from_seed(&[1; 32]).unwrap(), authority, nonce }
+    }
+
+    // An unsigned transfer that needs both the payer's and the nonce authority's signatures
+    fn unsigned(setup: &Setup, lamports: u64) -> String {
+        let transfer = system_instruction::transfer(&setup.payer.pubkey(), &Pubkey::new_from_array([9; 32]), lamports);
+        let options = TransactionOptions::default();
+        let transaction = build_nonce_transaction(&setup.payer.pubkey(), vec![transfer], &setup.nonce, &options);
+        encode_transaction(&transaction, ENCODING).unwrap()
+    }
+
+    fn merge_error(encoded: &[&str]) -> String {
+        match merge_signatures(encoded, ENCODING) {
+            Err(KeypairError::InvalidTransaction(reason)) => reason,
+            other => panic!("unexpected merge result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nonce_transaction_advances_nonce_first() {
+        let setup = setup();
+        let options = TransactionOptions {
+            priority_fee: Some(PriorityFee { compute_unit_limit: Some(200_000), compute_unit_price: Some(1) }),
+            memo: Some("offline".to_string()),
+        };
+        let transfer = system_instruction::transfer(&setup.payer.pubkey(), &Pubkey::new_from_array([9; 32]), 1);
+        let transaction = build_nonce_transaction(&setup.payer.pubkey(), vec![transfer], &setup.nonce, &options);
+        let message = &transaction.message;
+
+        assert_eq!(message.recent_blockhash, setup.nonce.nonce_value);
+        let first = &message.instructions[0];
+        assert_eq!(message.account_keys[first.program_id_index as usize], system_program::id());
+        assert_eq!(
+            bincode::deserialize::<SystemInstruction>(&first.data).unwrap(),
+            SystemInstruction::AdvanceNonceAccount
+        );
+        assert_eq!(message.account_keys[first.accounts[0] as usize], setup.nonce.nonce_account);
+
+        // Compute budget, transfer and memo follow in their usual order
+        let programs: Vec<Pubkey> = message.instructions[1..]
+            .iter()
+            .map(|instruction| message.account_keys[instruction.program_id_index as usize])
+            .collect();
+        let compute_budget = solana_sdk::compute_budget::id();
+        assert_eq!(programs, vec![compute_budget, compute_budget, system_program::id(), spl_memo::id()]);
+
+        assert_eq!(missing_signers(&transaction), vec![setup.payer.pubkey(), setup.authority.pubkey()]);
+    }
+
+    #[test]
+    fn partial_signatures_merge_into_signed_transaction() {
+        let setup = setup();
+        let unsigned = unsigned(&setup, 1_000);
+
+        // Each key holder signs their own copy offline
+        let payer_copy = partial_sign(&unsigned, ENCODING, &setup.payer).unwrap();
+        let authority_copy = partial_sign(&unsigned, ENCODING, &setup.authority).unwrap();
+        let payer_only = decode_transaction(&payer_copy, ENCODING).unwrap();
+        assert_eq!(missing_signers(&payer_only), vec![setup.authority.pubkey()]);
+
+        let merged = merge_signatures(&[&payer_copy, &authority_copy], ENCODING).unwrap();
+        let transaction = decode_transaction(&merged, ENCODING).unwrap();
+        assert!(missing_signers(&transaction).is_empty());
+        assert!(transaction.verify().is_ok());
+        assert_eq!(transaction.message.recent_blockhash, setup.nonce.nonce_value);
+
+        // Signing in turn gives the same transaction
+        let sequential = partial_sign(&payer_copy, ENCODING, &setup.authority).unwrap();
+        assert_eq!(sequential, merged);
+    }
+
+    #[test]
+    fn partial_sign_rejects_unrelated_signer() {
+        let setup = setup();
+        let stranger = keypair_from_seed(&[4; 32]).unwrap();
+        let result = partial_sign(&unsigned(&setup, 1_000), ENCODING, &stranger);
+        assert!(matches!(result, Err(KeypairError::SigningFailed(_))));
+    }
+
+    #[test]
+    fn merge_rejects_different_messages() {
+        let setup = setup();
+        let one = partial_sign(&unsigned(&setup, 1_000), ENCODING, &setup.payer).unwrap();
+        let other = partial_sign(&unsigned(&setup, 2_000), ENCODING, &setup.authority).unwrap();
+        assert!(merge_error(&[&one, &other]).contains("different messages"));
+        assert!(merge_error(&[]).contains("no transactions"));
+    }
+
+    #[test]
+    fn merge_rejects_conflicting_signatures() {
+        let setup = setup();
+        let signed = partial_sign(&unsigned(&setup, 1_000), ENCODING, &setup.payer).unwrap();
+        let mut forged = decode_transaction(&signed, ENCODING).unwrap();
+        forged.signatures[0] = Signature::from([9; 64]);
+        let forged = encode_transaction(&forged, ENCODING).unwrap();
+
+        assert!(merge_error(&[&signed, &forged]).contains("conflicting signatures"));
+    }
+
+    #[test]
+    fn merge_rejects_signature_that_does_not_verify() {
+        let setup = setup();
+        let mut forged = decode_transaction(&unsigned(&setup, 1_000), ENCODING).unwrap();
+        forged.signatures[1] = Signature::from([9; 64]);
+        let forged = encode_transaction(&forged, ENCODING).unwrap();
+
+        let reason = merge_error(&[&forged]);
+        assert_eq!(reason, format!("signature for {} does not verify", setup.authority.pubkey()));
+    }
+
+    #[test]
+    fn decodes_initialized_nonce_account() {
+        let setup = setup();
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_from_array([5; 32]));
+        let state = State::Initialized(Data::new(setup.authority.pubkey(), durable_nonce, 5_000));
+        let data = base64::encode(bincode::serialize(&Versions::new(state)).unwrap());
+
+        let address = setup.nonce.nonce_account.to_string();
+        let info = decode_nonce_account(&address, &data).unwrap();
+        assert_eq!(info.authority, setup.authority.pubkey());
+        assert_eq!(info.nonce_value, *durable_nonce.as_hash());
+        assert_eq!(info.lamports_per_signature, 5_000);
+
+        let uninitialized = base64::encode(bincode::serialize(&Versions::new(State::Uninitialized)).unwrap());
+        assert!(matches!(
+            decode_nonce_account(&address, &uninitialized),
+            Err(KeypairError::InvalidNonceAccount(_))
+        ));
+    }
+}
@@ -0,0 +1,314 @@
+// Program-derived addresses and seeded account addresses.
+//
+// A PDA is sha256(seeds || bump || program_id || "ProgramDerivedAddress") with the bump chosen
+// so the result is off the ed25519 curve, which means no private key exists for it and only
+// the program can sign for it.
+use crate::{parse_pubkey, KeypairError};
+use solana_sdk::pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN};
+use solana_sdk::{stake, system_program};
+use std::fmt;
+use std::str::FromStr;
+
+// Metaplex Token Metadata program
+pub const METADATA_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// One PDA seed. On the command line or from JS they are written as `type:value`, e.g.
+// `string:order`, `pubkey:<base58>`, `u64:7`, `u8:1` or `hex:deadbeef`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Seed {
+    String(String),
+    Pubkey(Pubkey),
+    // Encoded little-endian, as programs usually do with `to_le_bytes`
+    U64(u64),
+    U8(u8),
+    Bytes(Vec<u8>),
+}
+
+impl Seed {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Seed::String(value) => value.as_bytes().to_vec(),
+            Seed::Pubkey(value) => value.to_bytes().to_vec(),
+            Seed::U64(value) => value.to_le_bytes().to_vec(),
+            Seed::U8(value) => vec![*value],
+            Seed::Bytes(value) => value.clone(),
+        }
+    }
+}
+
+impl FromStr for Seed {
+    type Err = KeypairError;
+
+    fn from_str(spec: &str) -> Result<Seed, KeypairError> {
+        let invalid = |reason: String| KeypairError::InvalidSeed(format!("'{}': {}", spec, reason));
+        let (kind, value) = spec
+            .split_once(':')
+            .ok_or_else(|| invalid("expected type:value".to_string()))?;
+        match kind {
+            "string" => Ok(Seed::String(value.to_string())),
+            "pubkey" => Ok(Seed::Pubkey(parse_pubkey(value)?)),
+            "u64" => value.parse().map(Seed::U64).map_err(|e| invalid(format!("{}", e))),
+            "u8" => value.parse().map(Seed::U8).map_err(|e| invalid(format!("{}", e))),
+            "hex" => Ok(Seed::Bytes(hex::decode(value.trim_start_matches("0x"))?)),
+            other => Err(invalid(format!(
+                "unknown seed type '{}', expected string, pubkey, u64, u8 or hex",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Seed::String(value) => write!(f, "string:{}", value),
+            Seed::Pubkey(value) => write!(f, "pubkey:{}", value),
+            Seed::U64(value) => write!(f, "u64:{}", value),
+            Seed::U8(value) => write!(f, "u8:{}", value),
+            Seed::Bytes(value) => write!(f, "hex:{}", hex::encode(value)),
+        }
+    }
+}
+
+// Seed bytes, checked against the runtime's limits. One seed slot is left for the bump.
+fn seed_bytes(seeds: &[Seed]) -> Result<Vec<Vec<u8>>, KeypairError> {
+    if seeds.len() >= MAX_SEEDS {
+        return Err(KeypairError::InvalidSeed(format!(
+            "{} seeds given, at most {} fit alongside the bump",
+            seeds.len(),
+            MAX_SEEDS - 1
+        )));
+    }
+    seeds
+        .iter()
+        .map(|seed| {
+            let bytes = seed.to_bytes();
+            if bytes.len() > MAX_SEED_LEN {
+                return Err(KeypairError::InvalidSeed(format!(
+                    "{} is {} bytes, longer than {}",
+                    seed,
+                    bytes.len(),
+                    MAX_SEED_LEN
+                )));
+            }
+            Ok(bytes)
+        })
+        .collect()
+}
+
+// The canonical PDA: the address from the highest bump that lands off the curve
+pub fn find_program_address(seeds: &[Seed], program_id: &Pubkey) -> Result<(Pubkey, u8), KeypairError> {
+    let bytes = seed_bytes(seeds)?;
+    let slices: Vec<&[u8]> = bytes.iter().map(Vec::as_slice).collect();
+    Pubkey::try_find_program_address(&slices, program_id)
+        .ok_or_else(|| KeypairError::InvalidSeed("no bump yields an address off the curve".to_string()))
+}
+
+// The PDA for a specific bump, which fails if that bump lands on the curve
+pub fn create_program_address(seeds: &[Seed], bump: u8, program_id: &Pubkey) -> Result<Pubkey, KeypairError> {
+    let mut bytes = seed_bytes(seeds)?;
+    bytes.push(vec![bump]);
+    let slices: Vec<&[u8]> = bytes.iter().map(Vec::as_slice).collect();
+    Pubkey::create_program_address(&slices, program_id)
+        .map_err(|e| KeypairError::InvalidSeed(format!("bump {}: {}", bump, e)))
+}
+
+// Every bump that yields a valid PDA for these seeds, from 255 down
+pub fn valid_bumps(seeds: &[Seed], program_id: &Pubkey) -> Result<Vec<(u8, Pubkey)>, KeypairError> {
+    seed_bytes(seeds)?;
+    Ok((0..=u8::MAX)
+        .rev()
+        .filter_map(|bump| create_program_address(seeds, bump, program_id).ok().map(|address| (bump, address)))
+        .collect())
+}
+
+// Find which bump, if any, derives `address` from these seeds
+pub fn find_bump(address: &Pubkey, seeds: &[Seed], program_id: &Pubkey) -> Result<Option<u8>, KeypairError> {
+    Ok(valid_bumps(seeds, program_id)?
+        .into_iter()
+        .find(|(_, derived)| derived == address)
+        .map(|(bump, _)| bump))
+}
+
+// Whether an address is a point on the ed25519 curve, i.e. could have a private key.
+// PDAs are always off the curve.
+pub fn is_on_curve(address: &str) -> Result<bool, KeypairError> {
+    Ok(parse_pubkey(address)?.is_on_curve())
+}
+
+// sha256(base || seed || owner), as used by `solana create-stake-account --seed` and
+// `solana create-nonce-account --seed`
+pub fn create_with_seed(base: &Pubkey, seed: &str, owner: &Pubkey) -> Result<Pubkey, KeypairError> {
+    Pubkey::create_with_seed(base, seed, owner).map_err(|e| KeypairError::InvalidSeed(format!("'{}': {}", seed, e)))
+}
+
+pub fn stake_account_with_seed(base: &Pubkey, seed: &str) -> Result<Pubkey, KeypairError> {
+    create_with_seed(base, seed, &stake::program::id())
+}
+
+pub fn nonce_account_with_seed(base: &Pubkey, seed: &str) -> Result<Pubkey, KeypairError> {
+    create_with_seed(base, seed, &system_program::id())
+}
+
+// Metaplex metadata account for a mint: ["metadata", program id, mint]
+pub fn find_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+}
+
+// Metaplex master edition account for a mint: ["metadata", program id, mint, "edition"]
+pub fn find_master_edition_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref(), b"edition"],
+        &METADATA_PROGRAM_ID,
+    )
+}
+
+// Token marketplace addresses come from the program crate, so they always match what the
+// program derives
+pub use solana_token_marketplace::state::{
+    find_order_address as find_sell_order_address, find_seller_state_address,
+    find_vault_address as find_order_vault_address, ORDER_SEED as SELL_ORDER_SEED,
+    SELLER_SEED as SELLER_STATE_SEED, VAULT_SEED as ORDER_VAULT_SEED,
+};
+
+// Every sell order a seller has created for a mint, given the order_count from their seller
+// state. Orders that have since been filled or cancelled are closed, so some may not exist.
+pub fn find_sell_order_addresses(
+    program_id: &Pubkey,
+    marketplace: &Pubkey,
+    seller: &Pubkey,
+    mint: &Pubkey,
+    order_count: u64,
+) -> Vec<Pubkey> {
+    (0..order_count)
+        .map(|nonce| find_sell_order_address(program_id, marketplace, seller, mint, nonce).0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Expected addresses below were derived independently from the sha256 definitions above
+    const MINT: Pubkey = Pubkey::new_from_array([3; 32]);
+    const PROGRAM: Pubkey = Pubkey::new_from_array([4; 32]);
+    const BASE: Pubkey = Pubkey::new_from_array([5; 32]);
+
+    fn seeds() -> Vec<Seed> {
+        ["string:order", "pubkey:CktRuQ2mttgRGkXJtyksdKHjUdc2C4TgDzyB98oEzy8", "u64:7", "u8:1", "hex:0xdeadbeef"]
+            .iter()
+            .map(|spec| spec.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parses_and_displays_seeds() {
+        let seeds = seeds();
+        assert_eq!(
+            seeds,
+            vec![
+                Seed::String("order".to_string()),
+                Seed::Pubkey(MINT),
+                Seed::U64(7),
+                Seed::U8(1),
+                Seed::Bytes(vec![0xde, 0xad, 0xbe, 0xef]),
+            ]
+        );
+        assert_eq!(seeds[2].to_bytes(), vec![7, 0, 0, 0, 0, 0, 0, 0]);
+        let shown: Vec<String> = seeds.iter().map(Seed::to_string).collect();
+        assert_eq!(shown[4], "hex:deadbeef");
+        // Everything but the 0x prefix survives a round trip
+        for (seed, spec) in seeds.iter().zip(&shown) {
+            assert_eq!(&spec.parse::<Seed>().unwrap(), seed);
+        }
+        // Only the first colon separates the type
+        assert_eq!("string:a:b".parse::<Seed>().unwrap(), Seed::String("a:b".to_string()));
+    }
+
+    #[test]
+    fn rejects_bad_seeds() {
+        for spec in ["order", "u32:7", "u64:-1", "u8:256", "u64:seven"] {
+            assert!(
+                matches!(spec.parse::<Seed>(), Err(KeypairError::InvalidSeed(message)) if message.contains(spec)),
+                "{}",
+                spec
+            );
+        }
+        assert!(matches!("hex:xyzw".parse::<Seed>(), Err(KeypairError::InvalidHexCharacter { character: 'x', .. })));
+        assert!(matches!("pubkey:0OIl".parse::<Seed>(), Err(KeypairError::InvalidBase58Character { .. })));
+
+        let too_long = Seed::Bytes(vec![0; MAX_SEED_LEN + 1]);
+        assert!(matches!(find_program_address(&[too_long], &PROGRAM), Err(KeypairError::InvalidSeed(_))));
+        let too_many = vec![Seed::U8(0); MAX_SEEDS];
+        assert!(matches!(valid_bumps(&too_many, &PROGRAM), Err(KeypairError::InvalidSeed(_))));
+    }
+
+    #[test]
+    fn derives_known_program_addresses() {
+        let canonical = solana_sdk::pubkey!("6a5Yfp1MvXbBRV46nhd214UuppdPvjGcmz76gZBWab4R");
+        // Bump 255 lands on the curve for these seeds, so the canonical bump is 254
+        assert_eq!(find_program_address(&seeds(), &PROGRAM).unwrap(), (canonical, 254));
+        assert!(create_program_address(&seeds(), 255, &PROGRAM).is_err());
+        assert_eq!(create_program_address(&seeds(), 254, &PROGRAM).unwrap(), canonical);
+
+        let bumps = valid_bumps(&seeds(), &PROGRAM).unwrap();
+        assert_eq!(bumps.len(), 120);
+        assert_eq!(
+            bumps[..3],
+            [
+                (254, canonical),
+                (252, solana_sdk::pubkey!("2Sh8MvWjpifYvM2NrdbZNxkabv9PwqAFMRKrTDMJUoni")),
+                (251, solana_sdk::pubkey!("AMZyEnjRMPEUv658rxjk3p9zLrsZrzWexHxqv1EZmDoD")),
+            ]
+        );
+        assert!(bumps.iter().all(|(_, address)| !address.is_on_curve()));
+    }
+
+    #[test]
+    fn finds_the_bump_of_an_address() {
+        let other = solana_sdk::pubkey!("AMZyEnjRMPEUv658rxjk3p9zLrsZrzWexHxqv1EZmDoD");
+        assert_eq!(find_bump(&other, &seeds(), &PROGRAM).unwrap(), Some(251));
+        assert_eq!(find_bump(&MINT, &seeds(), &PROGRAM).unwrap(), None);
+        assert_eq!(find_bump(&other, &seeds()[1..], &PROGRAM).unwrap(), None);
+    }
+
+    #[test]
+    fn checks_whether_addresses_are_on_the_curve() {
+        // A real wallet, from keypair_from_seed(&[1; 32])
+        assert!(is_on_curve("AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9").unwrap());
+        assert!(!is_on_curve("6a5Yfp1MvXbBRV46nhd214UuppdPvjGcmz76gZBWab4R").unwrap());
+        assert!(!is_on_curve(&PROGRAM.to_string()).unwrap());
+        assert!(matches!(is_on_curve("11111"), Err(KeypairError::InvalidLength { actual: 5, .. })));
+    }
+
+    #[test]
+    fn derives_known_seeded_addresses() {
+        assert_eq!(
+            stake_account_with_seed(&BASE, "stake:0").unwrap(),
+            solana_sdk::pubkey!("C32MYktqfE3x5PhCExLpA9rJ2nJTsHsfZtKAifAzsU6L")
+        );
+        assert_eq!(
+            nonce_account_with_seed(&BASE, "nonce").unwrap(),
+            solana_sdk::pubkey!("6qqpgrAwefWjL5THrBupG6Sji9bAtbY9iEzAxREHyZw8")
+        );
+        assert!(matches!(
+            create_with_seed(&BASE, &"x".repeat(MAX_SEED_LEN + 1), &PROGRAM),
+            Err(KeypairError::InvalidSeed(_))
+        ));
+    }
+
+    #[test]
+    fn derives_known_metaplex_addresses() {
+        assert_eq!(
+            find_metadata_address(&MINT),
+            (solana_sdk::pubkey!("7AD2z3WLVQpLdUcyvwSsdXLeptJsQPyxrWDJNmi3cC7S"), 255)
+        );
+        assert_eq!(
+            find_master_edition_address(&MINT),
+            (solana_sdk::pubkey!("2bQhPpp18we5x5VBvUsfhfaaFjcN3sHsd9cBGkkyzr3g"), 251)
+        );
+    }
+}
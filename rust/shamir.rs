@@ -0,0 +1,543 @@
+// Shamir secret sharing for key backups: split a keypair's 32-byte secret or a seed phrase's
+// entropy into N shares so that any K of them rebuild it and fewer reveal nothing.
+//
+// Each byte of the secret is the constant term of its own random polynomial of degree K - 1
+// over GF(256), and share x holds every polynomial evaluated at x. A share is
+//
+//   version | kind | threshold | x | set id (4) | y bytes | checksum (4)
+//
+// where the set id ties together shares from one split and the checksum is the first four
+// bytes of sha256 over everything before it. Shares are written either as a string prefixed
+// with "solshare1:" or as words from the BIP39 English list, 11 bits per word.
+use crate::derivation::keypair_from_mnemonic_and_path;
+use crate::mnemonic::keypair_from_mnemonic;
+use crate::{parse_pubkey, KeypairError};
+use bip39::{Language, Mnemonic};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+use std::fmt;
+use std::str::FromStr;
+
+const SHARE_VERSION: u8 = 1;
+const STRING_PREFIX: &str = "solshare1:";
+const HEADER_LEN: usize = 8;
+const CHECKSUM_LEN: usize = 4;
+
+// What a set of shares rebuilds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretKind {
+    // The 32-byte ed25519 secret seed of a keypair
+    Keypair,
+    // The entropy behind a BIP39 seed phrase
+    MnemonicEntropy,
+}
+
+impl SecretKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            SecretKind::Keypair => 0,
+            SecretKind::MnemonicEntropy => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<SecretKind, KeypairError> {
+        match byte {
+            0 => Ok(SecretKind::Keypair),
+            1 => Ok(SecretKind::MnemonicEntropy),
+            other => Err(KeypairError::InvalidShare(format!("unknown secret kind {}", other))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    pub kind: SecretKind,
+    pub threshold: u8,
+    // The x coordinate, 1 to 255
+    pub index: u8,
+    pub set_id: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = Sha256::digest(bytes);
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+impl Share {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![SHARE_VERSION, self.kind.to_byte(), self.threshold, self.index];
+        bytes.extend_from_slice(&self.set_id);
+        bytes.extend_from_slice(&self.data);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Share, KeypairError> {
+        if bytes.len() <= HEADER_LEN + CHECKSUM_LEN {
+            return Err(KeypairError::InvalidShare(format!("{} bytes is too short", bytes.len())));
+        }
+        let (body, sum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(body) != sum {
+            return Err(KeypairError::InvalidShare("checksum mismatch".to_string()));
+        }
+        if body[0] != SHARE_VERSION {
+            return Err(KeypairError::InvalidShare(format!("unsupported version {}", body[0])));
+        }
+
+        let share = Share {
+            kind: SecretKind::from_byte(body[1])?,
+            threshold: body[2],
+            index: body[3],
+            set_id: [body[4], body[5], body[6], body[7]],
+            data: body[HEADER_LEN..].to_vec(),
+        };
+        if share.index == 0 || share.threshold < 2 {
+            return Err(KeypairError::InvalidShare("corrupt share header".to_string()));
+        }
+        Ok(share)
+    }
+
+    // The share as a single line, e.g. "solshare1:3yZe7..."
+    pub fn to_encoded_string(&self) -> String {
+        format!("{}{}", STRING_PREFIX, bs58::encode(self.to_bytes()).into_string())
+    }
+
+    // The share as BIP39 English words, for writing down on paper
+    pub fn to_words(&self) -> String {
+        let words = english_words();
+        let bytes = self.to_bytes();
+        let mut phrase = Vec::new();
+        let mut accumulator: u32 = 0;
+        let mut bits = 0;
+        for byte in bytes {
+            accumulator = (accumulator << 8) | byte as u32;
+            bits += 8;
+            while bits >= 11 {
+                bits -= 11;
+                phrase.push(words[((accumulator >> bits) & 0x7ff) as usize]);
+            }
+            accumulator &= (1 << bits) - 1;
+        }
+        if bits > 0 {
+            // Pad the last word with zero bits
+            phrase.push(words[((accumulator << (11 - bits)) & 0x7ff) as usize]);
+        }
+        phrase.join(" ")
+    }
+
+    pub fn from_words(phrase: &str) -> Result<Share, KeypairError> {
+        let words = english_words();
+        let mut bytes = Vec::new();
+        let mut accumulator: u32 = 0;
+        let mut bits = 0;
+        for word in phrase.split_whitespace() {
+            let value = words
+                .binary_search(&word.to_lowercase().as_str())
+                .map_err(|_| KeypairError::InvalidShare(format!("'{}' is not a BIP39 English word", word)))?;
+            accumulator = (accumulator << 11) | value as u32;
+            bits += 11;
+            while bits >= 8 {
+                bits -= 8;
+                bytes.push((accumulator >> bits) as u8);
+            }
+            accumulator &= (1 << bits) - 1;
+        }
+        if accumulator != 0 {
+            return Err(KeypairError::InvalidShare("trailing padding bits are not zero".to_string()));
+        }
+        // Eight or more padding bits decode to an extra zero byte; the checksum tells which
+        // reading is the share
+        match Share::from_bytes(&bytes) {
+            Err(_) if bytes.last() == Some(&0) => Share::from_bytes(&bytes[..bytes.len() - 1]),
+            result => result,
+        }
+    }
+}
+
+// The English list is sorted, so a word's position is its 11-bit value
+fn english_words() -> &'static [&'static str] {
+    Language::English.wordlist().get_words_by_prefix("")
+}
+
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_encoded_string())
+    }
+}
+
+// Accepts either the "solshare1:" string form or a word list
+impl FromStr for Share {
+    type Err = KeypairError;
+
+    fn from_str(share: &str) -> Result<Share, KeypairError> {
+        let share = share.trim();
+        match share.strip_prefix(STRING_PREFIX) {
+            Some(encoded) => Share::from_bytes(&bs58::decode(encoded).into_vec()?),
+            None => Share::from_words(share),
+        }
+    }
+}
+
+// Multiplication in GF(256) with the AES polynomial x^8 + x^4 + x^3 + x + 1, without lookup
+// tables so the timing does not depend on the secret
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+// a^254 is the multiplicative inverse of a non-zero a
+fn gf_inverse(a: u8) -> u8 {
+    let mut result = 1;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+// Split raw secret bytes into `count` shares, any `threshold` of which rebuild them
+pub fn split_secret(
+    secret: &[u8],
+    kind: SecretKind,
+    threshold: u8,
+    count: u8,
+) -> Result<Vec<Share>, KeypairError> {
+    if threshold < 2 || threshold > count {
+        return Err(KeypairError::InvalidShare(format!(
+            "threshold must be between 2 and the share count {}, got {}",
+            count, threshold
+        )));
+    }
+    if secret.is_empty() {
+        return Err(KeypairError::InvalidShare("secret is empty".to_string()));
+    }
+
+    let mut set_id = [0u8; 4];
+    OsRng.fill_bytes(&mut set_id);
+
+    let mut shares: Vec<Share> = (1..=count)
+        .map(|index| Share {
+            kind,
+            threshold,
+            index,
+            set_id,
+            data: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+
+    let mut coefficients = vec![0u8; threshold as usize - 1];
+    for byte in secret {
+        OsRng.fill_bytes(&mut coefficients);
+        for share in &mut shares {
+            // Horner's rule: f(x) = byte + c1 x + c2 x^2 + ...
+            let mut y = 0;
+            for coefficient in coefficients.iter().rev() {
+                y = gf_mul(y, share.index) ^ coefficient;
+            }
+            share.data.push(gf_mul(y, share.index) ^ byte);
+        }
+    }
+    Ok(shares)
+}
+
+// Rebuild the secret from at least `threshold` shares of one split
+pub fn combine_shares(shares: &[Share]) -> Result<(SecretKind, Vec<u8>), KeypairError> {
+    let first = shares
+        .first()
+        .ok_or_else(|| KeypairError::InvalidShare("no shares given".to_string()))?;
+    for share in shares {
+        if share.set_id != first.set_id || share.kind != first.kind || share.threshold != first.threshold {
+            return Err(KeypairError::InvalidShare(
+                "shares come from different splits".to_string(),
+            ));
+        }
+        if share.data.len() != first.data.len() {
+            return Err(KeypairError::InvalidShare("shares have different lengths".to_string()));
+        }
+    }
+
+    let mut selected: Vec<&Share> = Vec::new();
+    for share in shares {
+        if !selected.iter().any(|chosen| chosen.index == share.index) {
+            selected.push(share);
+        }
+    }
+    let threshold = first.threshold as usize;
+    if selected.len() < threshold {
+        return Err(KeypairError::InvalidShare(format!(
+            "{} distinct shares given, {} needed",
+            selected.len(),
+            threshold
+        )));
+    }
+    selected.truncate(threshold);
+
+    // Lagrange interpolation at x = 0; subtraction is xor in GF(256)
+    let weights: Vec<u8> = selected
+        .iter()
+        .map(|share| {
+            selected
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold(1, |weight, other| {
+                    gf_mul(weight, gf_mul(other.index, gf_inverse(other.index ^ share.index)))
+                })
+        })
+        .collect();
+    let secret = (0..first.data.len())
+        .map(|position| {
+            selected
+                .iter()
+                .zip(&weights)
+                .fold(0, |byte, (share, weight)| byte ^ gf_mul(share.data[position], *weight))
+        })
+        .collect();
+    Ok((first.kind, secret))
+}
+
+fn parse_shares(shares: &[&str]) -> Result<Vec<Share>, KeypairError> {
+    shares.iter().map(|share| share.parse()).collect()
+}
+
+fn check_address(recovered: &Keypair, expected_address: &str) -> Result<(), KeypairError> {
+    let expected = parse_pubkey(expected_address)?;
+    if recovered.pubkey() != expected {
+        return Err(KeypairError::RecoveredPubkeyMismatch {
+            expected: expected.to_string(),
+            recovered: recovered.pubkey().to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Split a keypair's secret into shares
+pub fn split_keypair(keypair: &Keypair, threshold: u8, count: u8) -> Result<Vec<Share>, KeypairError> {
+    split_secret(keypair.secret().as_bytes(), SecretKind::Keypair, threshold, count)
+}
+
+// Rebuild a keypair from shares, refusing to return it unless it is the expected address
+pub fn recover_keypair(shares: &[&str], expected_address: &str) -> Result<Keypair, KeypairError> {
+    let (kind, secret) = combine_shares(&parse_shares(shares)?)?;
+    if kind != SecretKind::Keypair {
+        return Err(KeypairError::InvalidShare("shares hold a seed phrase, not a keypair".to_string()));
+    }
+    let keypair = keypair_from_seed(&secret).map_err(|e| KeypairError::InvalidSecretKey(e.to_string()))?;
+    check_address(&keypair, expected_address)?;
+    Ok(keypair)
+}
+
+// Split a seed phrase's entropy into shares
+pub fn split_mnemonic(phrase: &str, threshold: u8, count: u8) -> Result<Vec<Share>, KeypairError> {
+    let mnemonic =
+        Mnemonic::from_phrase(phrase, Language::English).map_err(|e| KeypairError::InvalidMnemonic(e.to_string()))?;
+    split_secret(mnemonic.entropy(), SecretKind::MnemonicEntropy, threshold, count)
+}
+
+// Rebuild a seed phrase from shares and check that it derives the expected address, either
+// at `derivation_path` or, without one, the way `solana-keygen recover` does
+pub fn recover_mnemonic(
+    shares: &[&str],
+    passphrase: &str,
+    derivation_path: Option<&str>,
+    expected_address: &str,
+) -> Result<String, KeypairError> {
+    let (kind, entropy) = combine_shares(&parse_shares(shares)?)?;
+    if kind != SecretKind::MnemonicEntropy {
+        return Err(KeypairError::InvalidShare("shares hold a keypair, not a seed phrase".to_string()));
+    }
+    let phrase = Mnemonic::from_entropy(&entropy, Language::English)
+        .map_err(|e| KeypairError::InvalidMnemonic(e.to_string()))?
+        .into_phrase();
+
+    let keypair = match derivation_path {
+        Some(path) => keypair_from_mnemonic_and_path(&phrase, passphrase, path)?,
+        None => keypair_from_mnemonic(&phrase, passphrase)?,
+    };
+    check_address(&keypair, expected_address)?;
+    Ok(phrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mnemonic::generate_mnemonic;
+
+    // Fixed so that corruption tests do not depend on random share contents
+    fn fixed_share(length: usize) -> Share {
+        Share {
+            kind: SecretKind::Keypair,
+            threshold: 3,
+            index: 7,
+            set_id: [1, 2, 3, 4],
+            data: (0..length).map(|i| (i * 37 + 11) as u8).collect(),
+        }
+    }
+
+    fn random_secret(length: usize) -> Vec<u8> {
+        let mut secret = vec![0u8; length];
+        OsRng.fill_bytes(&mut secret);
+        secret
+    }
+
+    #[test]
+    fn gf_inverse_inverts_every_nonzero_element() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inverse(a)), 1, "{}", a);
+        }
+    }
+
+    #[test]
+    fn any_threshold_of_shares_combine_to_the_secret() {
+        for (threshold, count) in [(2, 2), (2, 3), (3, 5), (5, 5), (4, 10), (2, 255), (255, 255)] {
+            let secret = random_secret(32);
+            let shares = split_secret(&secret, SecretKind::Keypair, threshold, count).unwrap();
+            assert_eq!(shares.len(), count as usize);
+
+            let threshold = threshold as usize;
+            let first = &shares[..threshold];
+            let last = &shares[shares.len() - threshold..];
+            let every_other: Vec<Share> = shares
+                .iter()
+                .step_by(2)
+                .chain(shares.iter().skip(1).step_by(2))
+                .cloned()
+                .collect();
+            for subset in [first, last, &every_other[..threshold], &shares[..]] {
+                assert_eq!(combine_shares(subset).unwrap(), (SecretKind::Keypair, secret.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn fewer_than_threshold_shares_do_not_reveal_the_secret() {
+        let secret = random_secret(32);
+        let shares = split_secret(&secret, SecretKind::Keypair, 3, 5).unwrap();
+
+        // Two distinct shares, or three with a duplicate, are refused
+        assert!(matches!(combine_shares(&shares[..2]), Err(KeypairError::InvalidShare(_))));
+        let duplicated = [shares[0].clone(), shares[1].clone(), shares[1].clone()];
+        assert!(matches!(combine_shares(&duplicated), Err(KeypairError::InvalidShare(_))));
+
+        // Claiming a lower threshold interpolates the wrong polynomial
+        let lowered: Vec<Share> = shares[..2]
+            .iter()
+            .map(|share| Share { threshold: 2, ..share.clone() })
+            .collect();
+        assert_ne!(combine_shares(&lowered).unwrap().1, secret);
+    }
+
+    #[test]
+    fn rejects_invalid_split_parameters() {
+        for (threshold, count) in [(0, 3), (1, 3), (4, 3)] {
+            assert!(split_secret(&[1], SecretKind::Keypair, threshold, count).is_err());
+        }
+        assert!(split_secret(&[], SecretKind::Keypair, 2, 3).is_err());
+    }
+
+    #[test]
+    fn rejects_shares_from_different_splits() {
+        let secret = random_secret(32);
+        let first = split_secret(&secret, SecretKind::Keypair, 2, 3).unwrap();
+        let mut second = split_secret(&secret, SecretKind::Keypair, 2, 3).unwrap();
+        second[1].set_id = first[0].set_id.map(|byte| byte ^ 1);
+        assert!(combine_shares(&[first[0].clone(), second[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn string_encoding_round_trips() {
+        for length in [1, 16, 32] {
+            let share = fixed_share(length);
+            let encoded = share.to_encoded_string();
+            assert!(encoded.starts_with(STRING_PREFIX));
+            assert_eq!(encoded.parse::<Share>().unwrap(), share);
+            assert_eq!(Share::from_bytes(&share.to_bytes()).unwrap(), share);
+        }
+    }
+
+    #[test]
+    fn word_encoding_round_trips() {
+        // Every length, so each amount of padding in the last word is covered
+        for length in 1..=40 {
+            let share = fixed_share(length);
+            let words = share.to_words();
+            assert_eq!(Share::from_words(&words).unwrap(), share, "{} bytes", length);
+            assert_eq!(words.to_uppercase().parse::<Share>().unwrap(), share);
+        }
+    }
+
+    #[test]
+    fn rejects_corrupted_shares() {
+        let share = fixed_share(32);
+        let bytes = share.to_bytes();
+        for position in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[position] ^= 0x01;
+            assert!(Share::from_bytes(&corrupted).is_err(), "byte {} flipped", position);
+        }
+        assert!(Share::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let phrase = share.to_words();
+        let mut words: Vec<&str> = phrase.split(' ').collect();
+        words[3] = if words[3] == "abandon" { "ability" } else { "abandon" };
+        assert!(Share::from_words(&words.join(" ")).is_err());
+        words[3] = "solana";
+        assert!(Share::from_words(&words.join(" ")).is_err());
+
+        let encoded = share.to_encoded_string();
+        let last = encoded.chars().last().unwrap();
+        let replaced = format!("{}{}", &encoded[..encoded.len() - 1], if last == '2' { '3' } else { '2' });
+        assert!(replaced.parse::<Share>().is_err());
+    }
+
+    #[test]
+    fn keypair_round_trips_through_string_shares() {
+        let keypair = Keypair::new();
+        let address = keypair.pubkey().to_string();
+        let shares: Vec<String> = split_keypair(&keypair, 2, 3)
+            .unwrap()
+            .iter()
+            .map(Share::to_encoded_string)
+            .collect();
+
+        let recovered = recover_keypair(&[&shares[2], &shares[0]], &address).unwrap();
+        assert_eq!(recovered.to_bytes(), keypair.to_bytes());
+
+        assert!(recover_keypair(&[&shares[1]], &address).is_err());
+        let other = Keypair::new().pubkey().to_string();
+        assert!(matches!(
+            recover_keypair(&[&shares[0], &shares[1]], &other),
+            Err(KeypairError::RecoveredPubkeyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn mnemonic_round_trips_through_word_shares() {
+        for word_count in [12, 24] {
+            let phrase = generate_mnemonic(word_count).unwrap();
+            let address = keypair_from_mnemonic(&phrase, "").unwrap().pubkey().to_string();
+            let shares: Vec<String> = split_mnemonic(&phrase, 3, 5).unwrap().iter().map(Share::to_words).collect();
+
+            let recovered = recover_mnemonic(&[&shares[4], &shares[1], &shares[2]], "", None, &address).unwrap();
+            assert_eq!(recovered, phrase);
+
+            assert!(recover_mnemonic(&[&shares[0], &shares[1]], "", None, &address).is_err());
+            // Seed phrase shares are not mistaken for a keypair
+            assert!(recover_keypair(&[&shares[0], &shares[1], &shares[2]], &address).is_err());
+        }
+    }
+}
@@ -0,0 +1,197 @@
+// Message signing: Solana off-chain messages and raw detached ed25519 signatures.
+//
+// Off-chain messages are prefixed with the "\xffsolana offchain" signing domain, a version byte,
+// a format byte and a little-endian u16 length, so a signed message can never be replayed as a
+// transaction.
+use crate::{parse_pubkey, KeypairError};
+use base64::{decode, encode};
+use serde::{Deserialize, Serialize};
+use solana_sdk::offchain_message::{MessageFormat, OffchainMessage};
+use solana_sdk::signature::{Keypair, Signature, Signer};
+
+// The only off-chain message version defined so far
+pub const OFFCHAIN_MESSAGE_VERSION: u8 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureEncoding {
+    Base58,
+    Base64,
+}
+
+// A signed off-chain message, shaped for pasting into a support ticket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedMessage {
+    pub pubkey: String,
+    pub message: String,
+    pub signature: String,
+    pub encoding: SignatureEncoding,
+}
+
+pub fn encode_signature(signature: &Signature, encoding: SignatureEncoding) -> String {
+    match encoding {
+        SignatureEncoding::Base58 => signature.to_string(),
+        SignatureEncoding::Base64 => encode(signature.as_ref()),
+    }
+}
+
+pub fn decode_signature(signature: &str, encoding: SignatureEncoding) -> Result<Signature, KeypairError> {
+    let bytes = match encoding {
+        SignatureEncoding::Base58 => bs58::decode(signature.trim()).into_vec()?,
+        SignatureEncoding::Base64 => decode(signature.trim())?,
+    };
+    Signature::try_from(bytes.as_slice())
+        .map_err(|_| KeypairError::InvalidLength { expected: "64", actual: bytes.len() })
+}
+
+fn offchain_message(message: &[u8]) -> Result<OffchainMessage, KeypairError> {
+    OffchainMessage::new(OFFCHAIN_MESSAGE_VERSION, message).map_err(|e| KeypairError::InvalidMessage(e.to_string()))
+}
+
+// The exact bytes that get signed for an off-chain message, header included
+pub fn offchain_message_bytes(message: &[u8]) -> Result<Vec<u8>, KeypairError> {
+    offchain_message(message)?
+        .serialize()
+        .map_err(|e| KeypairError::InvalidMessage(e.to_string()))
+}
+
+// Whether a message will be signed as restricted ASCII, limited UTF-8 or extended UTF-8
+pub fn offchain_message_format(message: &[u8]) -> Result<MessageFormat, KeypairError> {
+    Ok(offchain_message(message)?.get_format())
+}
+
+// Sign a message in the off-chain message format, like `solana sign-offchain-message`
+pub fn sign_offchain_message(
+    keypair: &Keypair,
+    message: &[u8],
+    encoding: SignatureEncoding,
+) -> Result<String, KeypairError> {
+    let signature = offchain_message(message)?
+        .sign(keypair)
+        .map_err(|e| KeypairError::InvalidMessage(e.to_string()))?;
+    Ok(encode_signature(&signature, encoding))
+}
+
+// Check an off-chain message signature against a base58 pubkey
+pub fn verify_offchain_message(
+    pubkey: &str,
+    message: &[u8],
+    signature: &str,
+    encoding: SignatureEncoding,
+) -> Result<bool, KeypairError> {
+    let pubkey = parse_pubkey(pubkey)?;
+    let signature = decode_signature(signature, encoding)?;
+    offchain_message(message)?
+        .verify(&pubkey, &signature)
+        .map_err(|e| KeypairError::InvalidMessage(e.to_string()))
+}
+
+// Sign a UTF-8 message and bundle it with the signer's address
+pub fn sign_message_for_proof(
+    keypair: &Keypair,
+    message: &str,
+    encoding: SignatureEncoding,
+) -> Result<SignedMessage, KeypairError> {
+    Ok(SignedMessage {
+        pubkey: keypair.pubkey().to_string(),
+        message: message.to_string(),
+        signature: sign_offchain_message(keypair, message.as_bytes(), encoding)?,
+        encoding,
+    })
+}
+
+// Verify a bundle produced by `sign_message_for_proof`
+pub fn verify_message_proof(proof: &SignedMessage) -> Result<bool, KeypairError> {
+    verify_offchain_message(&proof.pubkey, proof.message.as_bytes(), &proof.signature, proof.encoding)
+}
+
+// Detached ed25519 signature over arbitrary bytes, with no domain separation
+pub fn sign_bytes(keypair: &Keypair, data: &[u8], encoding: SignatureEncoding) -> String {
+    encode_signature(&keypair.sign_message(data), encoding)
+}
+
+pub fn verify_bytes(
+    pubkey: &str,
+    data: &[u8],
+    signature: &str,
+    encoding: SignatureEncoding,
+) -> Result<bool, KeypairError> {
+    let pubkey = parse_pubkey(pubkey)?;
+    let signature = decode_signature(signature, encoding)?;
+    Ok(signature.verify(pubkey.as_ref(), data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::keypair_from_seed;
+
+    const ADDRESS: &str = "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9";
+
+    // The keypair with secret seed [1; 32]
+    fn keypair() -> Keypair {
+        keypair_from_seed(&[1; 32]).unwrap()
+    }
+
+    #[test]
+    fn offchain_message_bytes_match_the_spec() {
+        // "\xffsolana offchain", version 0, format, u16 LE length, message
+        let ascii = offchain_message_bytes(b"Hello, world!").unwrap();
+        assert_eq!(
+            hex::encode(ascii),
+            "ff736f6c616e61206f6666636861696e00000d0048656c6c6f2c20776f726c6421"
+        );
+        assert_eq!(offchain_message_format(b"Hello, world!").unwrap(), MessageFormat::RestrictedAscii);
+
+        let utf8 = offchain_message_bytes("héllo wörld".as_bytes()).unwrap();
+        assert_eq!(hex::encode(utf8), "ff736f6c616e61206f6666636861696e00010d0068c3a96c6c6f2077c3b6726c64");
+        assert_eq!(offchain_message_format("héllo wörld".as_bytes()).unwrap(), MessageFormat::LimitedUtf8);
+
+        assert!(offchain_message_bytes(b"").is_err());
+    }
+
+    #[test]
+    fn offchain_signature_matches_fixed_vector() {
+        let keypair = keypair();
+        assert_eq!(keypair.pubkey().to_string(), ADDRESS);
+
+        let signature = sign_offchain_message(&keypair, b"Hello, world!", SignatureEncoding::Base58).unwrap();
+        assert_eq!(
+            signature,
+            "5KJT3aY88Ldr1No4ckToDivBxTcMgzF9DG7LgqYVZx3RBUh6AaP7ztoMeQYfekZuucHKB5YFEpniPZsToH86yVoz"
+        );
+        assert!(verify_offchain_message(ADDRESS, b"Hello, world!", &signature, SignatureEncoding::Base58).unwrap());
+        assert!(!verify_offchain_message(ADDRESS, b"Hello, world?", &signature, SignatureEncoding::Base58).unwrap());
+
+        let signature = sign_offchain_message(&keypair, "héllo wörld".as_bytes(), SignatureEncoding::Base58).unwrap();
+        assert_eq!(
+            signature,
+            "3NdASm52HS4xxDuZTMvyH4ZaC7XnQnP9q2EFMrnbFpK3M4bfa2KzNrSQtHYWZ4b3tH6TUrzNqkaC3mfjYUXRVwet"
+        );
+    }
+
+    #[test]
+    fn raw_signatures_are_not_offchain_signatures() {
+        let keypair = keypair();
+        let raw = sign_bytes(&keypair, b"Hello, world!", SignatureEncoding::Base58);
+        assert_eq!(
+            raw,
+            "4bBytAeRXACUfZq3qtUEKC8BWjwwTeWTyXgfn5g2ggMjbxg7TNwgPbvbt14dUms6AhX8WS66mN1QDuraxyMQZHiK"
+        );
+        assert!(verify_bytes(ADDRESS, b"Hello, world!", &raw, SignatureEncoding::Base58).unwrap());
+        assert!(!verify_offchain_message(ADDRESS, b"Hello, world!", &raw, SignatureEncoding::Base58).unwrap());
+    }
+
+    #[test]
+    fn proofs_round_trip_in_both_encodings() {
+        for encoding in [SignatureEncoding::Base58, SignatureEncoding::Base64] {
+            let proof = sign_message_for_proof(&keypair(), "I own this address", encoding).unwrap();
+            assert_eq!(proof.pubkey, ADDRESS);
+            assert!(verify_message_proof(&proof).unwrap());
+
+            let tampered = SignedMessage { message: "I own that address".to_string(), ..proof };
+            assert!(!verify_message_proof(&tampered).unwrap());
+        }
+        assert!(decode_signature("AAAA", SignatureEncoding::Base64).is_err());
+    }
+}
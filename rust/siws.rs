@@ -0,0 +1,520 @@
+// Sign-In With Solana (SIWS): build, parse and verify the sign-in message wallets show
+// the user, in the same text layout as the Solana wallet standard's `signIn`.
+//
+//   example.com wants you to sign in with your Solana account:
+//   <address>
+//
+//   <statement>
+//
+//   URI: https://example.com
+//   Version: 1
+//   Chain ID: mainnet
+//   Nonce: 32891756
+//   Issued At: 2021-09-30T16:25:24.000Z
+//   ...
+//
+// Wallets sign the UTF-8 text directly, so verification uses a raw detached signature.
+use crate::signing::{verify_bytes, SignatureEncoding};
+use crate::{parse_pubkey, KeypairError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::distributions::Alphanumeric;
+use rand::rngs::OsRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const HEADER_SUFFIX: &str = " wants you to sign in with your Solana account:";
+const NONCE_LENGTH: usize = 16;
+const MIN_NONCE_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignInMessage {
+    pub domain: String,
+    pub address: String,
+    pub statement: Option<String>,
+    pub uri: Option<String>,
+    pub version: Option<String>,
+    pub chain_id: Option<String>,
+    pub nonce: Option<String>,
+    // RFC 3339 timestamps
+    pub issued_at: Option<String>,
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+// What the server expects of a sign-in it issued
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignInExpectations {
+    pub domain: String,
+    pub nonce: String,
+    pub chain_id: Option<String>,
+    pub now: DateTime<Utc>,
+}
+
+impl SignInExpectations {
+    pub fn new(domain: &str, nonce: &str) -> Self {
+        SignInExpectations {
+            domain: domain.to_string(),
+            nonce: nonce.to_string(),
+            chain_id: None,
+            now: Utc::now(),
+        }
+    }
+}
+
+// Random alphanumeric nonce for a new sign-in request
+pub fn generate_nonce() -> String {
+    OsRng
+        .sample_iter(&Alphanumeric)
+        .take(NONCE_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+impl SignInMessage {
+    // Start a version 1 message for `address`, issued now with a fresh nonce
+    pub fn new(domain: &str, address: &str) -> Self {
+        SignInMessage {
+            domain: domain.to_string(),
+            address: address.to_string(),
+            version: Some("1".to_string()),
+            nonce: Some(generate_nonce()),
+            issued_at: Some(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+            ..SignInMessage::default()
+        }
+    }
+
+    // Render the text the wallet signs. Fails if `parse` would read the text back differently.
+    pub fn to_text(&self) -> Result<String, KeypairError> {
+        self.check_readable()?;
+        let mut text = format!("{}{}\n{}", self.domain, HEADER_SUFFIX, self.address);
+        if let Some(statement) = &self.statement {
+            text.push_str("\n\n");
+            text.push_str(statement);
+        }
+
+        let mut fields = Vec::new();
+        let optional = [
+            ("URI", &self.uri),
+            ("Version", &self.version),
+            ("Chain ID", &self.chain_id),
+            ("Nonce", &self.nonce),
+            ("Issued At", &self.issued_at),
+            ("Expiration Time", &self.expiration_time),
+            ("Not Before", &self.not_before),
+            ("Request ID", &self.request_id),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                fields.push(format!("{}: {}", name, value));
+            }
+        }
+        if !self.resources.is_empty() {
+            fields.push("Resources:".to_string());
+            fields.extend(self.resources.iter().map(|resource| format!("- {}", resource)));
+        }
+
+        if !fields.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(&fields.join("\n"));
+        }
+        Ok(text)
+    }
+
+    // Every value sits on its own line, so none may span lines, and a statement that is empty
+    // or starts like a field would be taken for the end of the statement block
+    fn check_readable(&self) -> Result<(), KeypairError> {
+        if let Some(statement) = &self.statement {
+            if statement.is_empty() || is_field(statement) {
+                return Err(KeypairError::InvalidSignInMessage(format!(
+                    "statement '{}' would be read back as a field",
+                    statement
+                )));
+            }
+        }
+        let fields = [
+            &self.uri,
+            &self.version,
+            &self.chain_id,
+            &self.nonce,
+            &self.issued_at,
+            &self.expiration_time,
+            &self.not_before,
+            &self.request_id,
+        ];
+        let mut values = [&self.domain, &self.address]
+            .into_iter()
+            .chain(self.statement.iter())
+            .chain(fields.into_iter().flatten())
+            .chain(self.resources.iter());
+        match values.find(|value| value.contains(['\n', '\r'])) {
+            Some(value) => Err(KeypairError::InvalidSignInMessage(format!("'{}' spans more than one line", value))),
+            None => Ok(()),
+        }
+    }
+
+    // Parse the text form back into its fields
+    pub fn parse(text: &str) -> Result<SignInMessage, KeypairError> {
+        let invalid = |reason: &str| KeypairError::InvalidSignInMessage(reason.to_string());
+        let mut lines = text.split('\n').peekable();
+
+        let domain = lines
+            .next()
+            .and_then(|line| line.strip_suffix(HEADER_SUFFIX))
+            .filter(|domain| !domain.is_empty())
+            .ok_or_else(|| invalid("missing sign-in header"))?;
+        let address = lines.next().ok_or_else(|| invalid("missing address"))?;
+        parse_pubkey(address)?;
+
+        let mut message = SignInMessage {
+            domain: domain.to_string(),
+            address: address.to_string(),
+            ..SignInMessage::default()
+        };
+
+        if lines.peek().is_none() {
+            return Ok(message);
+        }
+        if lines.next() != Some("") {
+            return Err(invalid("expected a blank line after the address"));
+        }
+
+        // A statement is any line that is not a field
+        if let Some(line) = lines.peek() {
+            if !is_field(line) {
+                message.statement = Some(line.to_string());
+                lines.next();
+                if lines.peek().is_none() {
+                    return Ok(message);
+                }
+                if lines.next() != Some("") {
+                    return Err(invalid("expected a blank line after the statement"));
+                }
+            }
+        }
+
+        while let Some(line) = lines.next() {
+            if line == "Resources:" {
+                for resource in lines.by_ref() {
+                    let resource = resource
+                        .strip_prefix("- ")
+                        .ok_or_else(|| invalid("resources must be listed as '- <uri>'"))?;
+                    message.resources.push(resource.to_string());
+                }
+                break;
+            }
+
+            let (name, value) = line
+                .split_once(": ")
+                .ok_or_else(|| KeypairError::InvalidSignInMessage(format!("malformed field '{}'", line)))?;
+            let slot = match name {
+                "URI" => &mut message.uri,
+                "Version" => &mut message.version,
+                "Chain ID" => &mut message.chain_id,
+                "Nonce" => &mut message.nonce,
+                "Issued At" => &mut message.issued_at,
+                "Expiration Time" => &mut message.expiration_time,
+                "Not Before" => &mut message.not_before,
+                "Request ID" => &mut message.request_id,
+                other => return Err(KeypairError::InvalidSignInMessage(format!("unknown field '{}'", other))),
+            };
+            if slot.is_some() {
+                return Err(KeypairError::InvalidSignInMessage(format!("duplicate field '{}'", name)));
+            }
+            *slot = Some(value.to_string());
+        }
+
+        Ok(message)
+    }
+}
+
+fn is_field(line: &str) -> bool {
+    const FIELDS: [&str; 8] = [
+        "URI: ",
+        "Version: ",
+        "Chain ID: ",
+        "Nonce: ",
+        "Issued At: ",
+        "Expiration Time: ",
+        "Not Before: ",
+        "Request ID: ",
+    ];
+    line == "Resources:" || FIELDS.iter().any(|field| line.starts_with(field))
+}
+
+fn parse_time(name: &str, value: &str) -> Result<DateTime<Utc>, KeypairError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| KeypairError::InvalidSignInMessage(format!("{} '{}': {}", name, value, e)))
+}
+
+// Verify a signed sign-in message and return its parsed fields.
+//
+// Checks the signature against the message's own address, that the message is bound to the
+// expected domain, nonce and (if given) chain, and that `now` falls inside its validity window.
+pub fn verify_sign_in(
+    text: &str,
+    signature: &str,
+    encoding: SignatureEncoding,
+    expected: &SignInExpectations,
+) -> Result<SignInMessage, KeypairError> {
+    let message = SignInMessage::parse(text)?;
+
+    if !verify_bytes(&message.address, text.as_bytes(), signature, encoding)? {
+        return Err(KeypairError::SignatureVerificationFailed);
+    }
+
+    if message.domain != expected.domain {
+        return Err(KeypairError::SignInRejected(format!(
+            "domain {} does not match {}",
+            message.domain, expected.domain
+        )));
+    }
+
+    match &message.nonce {
+        Some(nonce) if nonce.len() >= MIN_NONCE_LENGTH && *nonce == expected.nonce => {}
+        Some(_) => return Err(KeypairError::SignInRejected("nonce does not match".to_string())),
+        None => return Err(KeypairError::SignInRejected("message has no nonce".to_string())),
+    }
+
+    if let Some(chain_id) = &expected.chain_id {
+        if message.chain_id.as_ref() != Some(chain_id) {
+            return Err(KeypairError::SignInRejected(format!("message is not for chain {}", chain_id)));
+        }
+    }
+
+    if let Some(issued_at) = &message.issued_at {
+        if parse_time("Issued At", issued_at)? > expected.now {
+            return Err(KeypairError::SignInRejected("message is issued in the future".to_string()));
+        }
+    }
+    if let Some(expiration_time) = &message.expiration_time {
+        if parse_time("Expiration Time", expiration_time)? <= expected.now {
+            return Err(KeypairError::SignInRejected(format!("message expired at {}", expiration_time)));
+        }
+    }
+    if let Some(not_before) = &message.not_before {
+        if parse_time("Not Before", not_before)? > expected.now {
+            return Err(KeypairError::SignInRejected(format!("message is not valid before {}", not_before)));
+        }
+    }
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::sign_bytes;
+    use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+
+    const NONCE: &str = "32891756abcdef";
+
+    // The keypair with secret seed [1; 32]
+    fn keypair() -> Keypair {
+        keypair_from_seed(&[1; 32]).unwrap()
+    }
+
+    fn full_message() -> SignInMessage {
+        SignInMessage {
+            domain: "example.com".to_string(),
+            address: keypair().pubkey().to_string(),
+            statement: Some("Sign in to Example".to_string()),
+            uri: Some("https://example.com/login".to_string()),
+            version: Some("1".to_string()),
+            chain_id: Some("mainnet".to_string()),
+            nonce: Some(NONCE.to_string()),
+            issued_at: Some("2021-09-30T16:25:24.000Z".to_string()),
+            expiration_time: Some("2021-09-30T17:25:24.000Z".to_string()),
+            not_before: Some("2021-09-30T16:25:24.000Z".to_string()),
+            request_id: Some("request-1".to_string()),
+            resources: vec!["https://example.com/a".to_string(), "ipfs://b".to_string()],
+        }
+    }
+
+    fn at(time: &str) -> SignInExpectations {
+        SignInExpectations {
+            now: parse_time("now", time).unwrap(),
+            ..SignInExpectations::new("example.com", NONCE)
+        }
+    }
+
+    fn sign(message: &SignInMessage) -> (String, String) {
+        let text = message.to_text().unwrap();
+        let signature = sign_bytes(&keypair(), text.as_bytes(), SignatureEncoding::Base58);
+        (text, signature)
+    }
+
+    fn assert_rejected(result: Result<SignInMessage, KeypairError>) {
+        assert!(matches!(result, Err(KeypairError::SignInRejected(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn renders_the_wallet_standard_layout() {
+        let expected = "example.com wants you to sign in with your Solana account:
+AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9
+
+Sign in to Example
+
+URI: https://example.com/login
+Version: 1
+Chain ID: mainnet
+Nonce: 32891756abcdef
+Issued At: 2021-09-30T16:25:24.000Z
+Expiration Time: 2021-09-30T17:25:24.000Z
+Not Before: 2021-09-30T16:25:24.000Z
+Request ID: request-1
+Resources:
+- https://example.com/a
+- ipfs://b";
+        assert_eq!(full_message().to_text().unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_round_trips_to_text() {
+        let full = full_message();
+        let minimal = SignInMessage {
+            domain: full.domain.clone(),
+            address: full.address.clone(),
+            ..SignInMessage::default()
+        };
+        let statement_only = SignInMessage {
+            statement: full.statement.clone(),
+            ..minimal.clone()
+        };
+        let fields_only = SignInMessage {
+            statement: None,
+            resources: Vec::new(),
+            ..full.clone()
+        };
+        for message in [full, minimal, statement_only, fields_only] {
+            assert_eq!(SignInMessage::parse(&message.to_text().unwrap()).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn refuses_to_render_values_that_would_read_back_differently() {
+        for statement in [
+            "Sign in\n\nURI: https://evil.example",
+            "URI: https://evil.example",
+            "Resources:",
+            "",
+            "a\r\nb",
+        ] {
+            let message = SignInMessage {
+                statement: Some(statement.to_string()),
+                ..full_message()
+            };
+            assert!(matches!(message.to_text(), Err(KeypairError::InvalidSignInMessage(_))), "{:?}", statement);
+        }
+        let message = SignInMessage {
+            request_id: Some("1\nNonce: attacker".to_string()),
+            nonce: None,
+            ..full_message()
+        };
+        assert!(matches!(message.to_text(), Err(KeypairError::InvalidSignInMessage(_))));
+        let message = SignInMessage {
+            resources: vec!["https://example.com\nhttps://evil.example".to_string()],
+            ..full_message()
+        };
+        assert!(matches!(message.to_text(), Err(KeypairError::InvalidSignInMessage(_))));
+
+        // A statement that only mentions a field name is fine
+        let message = SignInMessage {
+            statement: Some("Check the URI: it should be example.com".to_string()),
+            ..full_message()
+        };
+        assert_eq!(SignInMessage::parse(&message.to_text().unwrap()).unwrap(), message);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_messages() {
+        let text = full_message().to_text().unwrap();
+        for malformed in [
+            text.replacen(" wants you to sign in", " would like you to sign in", 1),
+            text.replacen("Request ID:", "Session:", 1),
+            text.replacen("Version: 1", "Version: 1\nVersion: 2", 1),
+            text.replacen("- ipfs://b", "ipfs://b", 1),
+            text.replacen("AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9", "not-an-address", 1),
+        ] {
+            assert!(SignInMessage::parse(&malformed).is_err(), "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn verifies_a_signed_message_inside_its_window() {
+        let (text, signature) = sign(&full_message());
+        let expected = SignInExpectations {
+            chain_id: Some("mainnet".to_string()),
+            ..at("2021-09-30T16:30:00Z")
+        };
+        let message = verify_sign_in(&text, &signature, SignatureEncoding::Base58, &expected).unwrap();
+        assert_eq!(message, full_message());
+    }
+
+    #[test]
+    fn rejects_wrong_signature() {
+        let (text, _) = sign(&full_message());
+        let signature = sign_bytes(&Keypair::new(), text.as_bytes(), SignatureEncoding::Base58);
+        let result = verify_sign_in(&text, &signature, SignatureEncoding::Base58, &at("2021-09-30T16:30:00Z"));
+        assert_eq!(result.unwrap_err(), KeypairError::SignatureVerificationFailed);
+    }
+
+    #[test]
+    fn rejects_wrong_domain() {
+        let (text, signature) = sign(&full_message());
+        let expected = SignInExpectations {
+            domain: "evil.example".to_string(),
+            ..at("2021-09-30T16:30:00Z")
+        };
+        assert_rejected(verify_sign_in(&text, &signature, SignatureEncoding::Base58, &expected));
+    }
+
+    #[test]
+    fn rejects_wrong_or_missing_nonce() {
+        let (text, signature) = sign(&full_message());
+        let expected = SignInExpectations {
+            nonce: "some-other-nonce".to_string(),
+            ..at("2021-09-30T16:30:00Z")
+        };
+        assert_rejected(verify_sign_in(&text, &signature, SignatureEncoding::Base58, &expected));
+
+        // Too short to be worth matching, even when it is what the server expects
+        let short = SignInMessage { nonce: Some("1234".to_string()), ..full_message() };
+        let (text, signature) = sign(&short);
+        let expected = SignInExpectations { nonce: "1234".to_string(), ..at("2021-09-30T16:30:00Z") };
+        assert_rejected(verify_sign_in(&text, &signature, SignatureEncoding::Base58, &expected));
+
+        let missing = SignInMessage { nonce: None, ..full_message() };
+        let (text, signature) = sign(&missing);
+        assert_rejected(verify_sign_in(&text, &signature, SignatureEncoding::Base58, &at("2021-09-30T16:30:00Z")));
+    }
+
+    #[test]
+    fn rejects_wrong_chain() {
+        let (text, signature) = sign(&full_message());
+        let expected = SignInExpectations {
+            chain_id: Some("devnet".to_string()),
+            ..at("2021-09-30T16:30:00Z")
+        };
+        assert_rejected(verify_sign_in(&text, &signature, SignatureEncoding::Base58, &expected));
+    }
+
+    #[test]
+    fn rejects_outside_the_validity_window() {
+        let (text, signature) = sign(&full_message());
+        // Expired, exactly at expiry, and before the message was issued
+        for now in ["2021-09-30T18:00:00Z", "2021-09-30T17:25:24Z", "2021-09-30T16:00:00Z"] {
+            assert_rejected(verify_sign_in(&text, &signature, SignatureEncoding::Base58, &at(now)));
+        }
+
+        let not_yet = SignInMessage {
+            not_before: Some("2021-09-30T17:00:00.000Z".to_string()),
+            ..full_message()
+        };
+        let (text, signature) = sign(&not_yet);
+        assert_rejected(verify_sign_in(&text, &signature, SignatureEncoding::Base58, &at("2021-09-30T16:30:00Z")));
+    }
+}
@@ -0,0 +1,155 @@
+// Associated token accounts for both the original SPL Token program and Token-2022
+use crate::{parse_pubkey, KeypairError};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+// Token programs an associated token account can belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenProgram {
+    Token,
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn id(&self) -> Pubkey {
+        match self {
+            TokenProgram::Token => spl_token::id(),
+            TokenProgram::Token2022 => spl_token_2022::id(),
+        }
+    }
+
+    // Pick the token program from the owner of a mint account, as returned by getAccountInfo
+    pub fn from_mint_owner(owner: &Pubkey) -> Result<TokenProgram, KeypairError> {
+        if *owner == spl_token::id() {
+            Ok(TokenProgram::Token)
+        } else if *owner == spl_token_2022::id() {
+            Ok(TokenProgram::Token2022)
+        } else {
+            Err(KeypairError::UnsupportedTokenProgram(owner.to_string()))
+        }
+    }
+}
+
+// Format a raw token amount with the mint's decimals, e.g. 1500000 with 6 decimals is "1.5"
+pub fn format_token_amount(amount: u64, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let digits = format!("{:0>width$}", amount, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+// Derive the associated token address under any token program
+pub fn get_associated_token_address_with_program_id(
+    wallet_address: &str,
+    token_mint: &str,
+    token_program_id: &str,
+) -> Result<String, KeypairError> {
+    let wallet_pubkey = parse_pubkey(wallet_address)?;
+    let token_mint_pubkey = parse_pubkey(token_mint)?;
+    let token_program_pubkey = parse_pubkey(token_program_id)?;
+
+    let associated_token_address = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &wallet_pubkey,
+        &token_mint_pubkey,
+        &token_program_pubkey,
+    );
+
+    Ok(associated_token_address.to_string())
+}
+
+// Derive the associated token address for a mint, choosing the program from the mint's owner
+pub fn get_associated_token_address_for_mint_owner(
+    wallet_address: &str,
+    token_mint: &str,
+    mint_owner: &str,
+) -> Result<String, KeypairError> {
+    let program = TokenProgram::from_mint_owner(&parse_pubkey(mint_owner)?)?;
+    get_associated_token_address_with_program_id(wallet_address, token_mint, &program.id().to_string())
+}
+
+// Build an instruction that creates the associated token account, succeeding
+// without changes if it already exists
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: TokenProgram,
+) -> Instruction {
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        payer,
+        wallet,
+        token_mint,
+        &token_program.id(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+    use solana_sdk::system_program;
+
+    const WALLET: &str = "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9";
+    const MINT: &str = "CktRuQ2mttgRGkXJtyksdKHjUdc2C4TgDzyB98oEzy8";
+    // Derived independently as the PDA [wallet, token program, mint] of the associated token program
+    const TOKEN_ATA: &str = "Dy41HfQhwNsnQRhKkxL6fGnmAKmQdhAM1h6KStEeWXys";
+    const TOKEN_2022_ATA: &str = "9zSrL2GkG2c44qLqdb11v2zuUxkBXLuwt6N2gQYKJu1e";
+    const TOKEN_2022_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+    #[test]
+    fn derives_associated_token_addresses_per_program() {
+        assert_eq!(crate::get_associated_token_address(WALLET, MINT).unwrap(), TOKEN_ATA);
+        assert_eq!(get_associated_token_address_with_program_id(WALLET, MINT, TOKEN_2022_ID).unwrap(), TOKEN_2022_ATA);
+        assert_eq!(
+            get_associated_token_address_for_mint_owner(WALLET, MINT, &spl_token::id().to_string()).unwrap(),
+            TOKEN_ATA
+        );
+        assert_eq!(get_associated_token_address_for_mint_owner(WALLET, MINT, TOKEN_2022_ID).unwrap(), TOKEN_2022_ATA);
+        assert_eq!(
+            get_associated_token_address_for_mint_owner(WALLET, MINT, WALLET),
+            Err(KeypairError::UnsupportedTokenProgram(WALLET.to_string()))
+        );
+    }
+
+    #[test]
+    fn builds_create_idempotent_with_the_chosen_program() {
+        let payer = Pubkey::new_from_array([9; 32]);
+        let wallet = parse_pubkey(WALLET).unwrap();
+        let mint = parse_pubkey(MINT).unwrap();
+        let instruction = create_associated_token_account_idempotent(&payer, &wallet, &mint, TokenProgram::Token2022);
+        assert_eq!(instruction.program_id, spl_associated_token_account::id());
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(parse_pubkey(TOKEN_2022_ATA).unwrap(), false),
+                AccountMeta::new_readonly(wallet, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(parse_pubkey(TOKEN_2022_ID).unwrap(), false),
+            ]
+        );
+        // 1 is CreateIdempotent; 0 would fail if the account already exists
+        assert_eq!(instruction.data, vec![1]);
+    }
+
+    #[test]
+    fn formats_token_amounts() {
+        assert_eq!(format_token_amount(1_500_000, 6), "1.5");
+        assert_eq!(format_token_amount(42, 0), "42");
+        assert_eq!(format_token_amount(0, 0), "0");
+        assert_eq!(format_token_amount(0, 9), "0");
+        assert_eq!(format_token_amount(5, 9), "0.000000005");
+        assert_eq!(format_token_amount(1_000_000_000, 9), "1");
+        assert_eq!(format_token_amount(1_230_000_000, 9), "1.23");
+        assert_eq!(format_token_amount(u64::MAX, 0), "18446744073709551615");
+        assert_eq!(format_token_amount(u64::MAX, 9), "18446744073.709551615");
+        assert_eq!(format_token_amount(u64::MAX, 20), "0.18446744073709551615");
+        assert_eq!(format_token_amount(u64::MAX, u8::MAX).len(), 2 + u8::MAX as usize);
+    }
+}
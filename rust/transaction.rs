@@ -0,0 +1,391 @@
+// Offline transaction building: everything is signed locally against a recent blockhash the
+// caller fetched, and the serialized result can be broadcast from anywhere with sendTransaction.
+use crate::token::TokenProgram;
+use crate::{parse_pubkey, KeypairError};
+use base64::{decode, encode};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionEncoding {
+    Base58,
+    Base64,
+}
+
+// Compute budget settings prepended to a transaction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PriorityFee {
+    pub compute_unit_limit: Option<u32>,
+    // Price per compute unit, in micro-lamports
+    pub compute_unit_price: Option<u64>,
+}
+
+impl PriorityFee {
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        if let Some(limit) = self.compute_unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+        }
+        if let Some(price) = self.compute_unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+        instructions
+    }
+}
+
+// Optional extras shared by every builder
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionOptions {
+    pub priority_fee: Option<PriorityFee>,
+    pub memo: Option<String>,
+}
+
+pub fn parse_blockhash(blockhash: &str) -> Result<Hash, KeypairError> {
+    Hash::from_str(blockhash.trim()).map_err(|e| KeypairError::InvalidBlockhash(format!("{}: {}", blockhash, e)))
+}
+
+pub fn memo_instruction(memo: &str, signers: &[&Pubkey]) -> Instruction {
+    spl_memo::build_memo(memo.as_bytes(), signers)
+}
+
+// Put the compute budget in front of the instructions and the memo, signed by the fee payer, at the end
+pub fn apply_options(fee_payer: &Pubkey, instructions: Vec<Instruction>, options: &TransactionOptions) -> Vec<Instruction> {
+    let mut all_instructions = options.priority_fee.map(|fee| fee.instructions()).unwrap_or_default();
+    all_instructions.extend(instructions);
+    if let Some(memo) = &options.memo {
+        all_instructions.push(memo_instruction(memo, &[fee_payer]));
+    }
+    all_instructions
+}
+
+// Build and sign a transaction paid for by `payer`
+pub fn build_transaction(
+    payer: &Keypair,
+    instructions: Vec<Instruction>,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    build_transaction_with_signers(payer, &[], instructions, recent_blockhash, options)
+}
+
+// Build and sign a transaction that needs signatures besides the fee payer's
+pub fn build_transaction_with_signers(
+    payer: &Keypair,
+    other_signers: &[&Keypair],
+    instructions: Vec<Instruction>,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let blockhash = parse_blockhash(recent_blockhash)?;
+    let instructions = apply_options(&payer.pubkey(), instructions, options);
+
+    let mut signers = vec![payer];
+    signers.extend_from_slice(other_signers);
+
+    let message = Message::new(&instructions, Some(&payer.pubkey()));
+    let mut transaction = Transaction::new_unsigned(message);
+    transaction
+        .try_sign(&signers, blockhash)
+        .map_err(|e| KeypairError::SigningFailed(e.to_string()))?;
+    Ok(transaction)
+}
+
+// Transfer SOL from the signer to `recipient`
+pub fn build_sol_transfer(
+    from: &Keypair,
+    recipient: &str,
+    lamports: u64,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let recipient = parse_pubkey(recipient)?;
+    let instruction = system_instruction::transfer(&from.pubkey(), &recipient, lamports);
+    build_transaction(from, vec![instruction], recent_blockhash, options)
+}
+
+// Transfer SPL tokens between the owners' associated token accounts with `transfer_checked`.
+// The recipient's account is created first if it does not exist yet, paid for by the sender.
+#[allow(clippy::too_many_arguments)]
+pub fn build_spl_transfer(
+    owner: &Keypair,
+    recipient_wallet: &str,
+    token_mint: &str,
+    amount: u64,
+    decimals: u8,
+    token_program: TokenProgram,
+    recent_blockhash: &str,
+    options: &TransactionOptions,
+) -> Result<Transaction, KeypairError> {
+    let recipient = parse_pubkey(recipient_wallet)?;
+    let mint = parse_pubkey(token_mint)?;
+    let program_id = token_program.id();
+
+    let source = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &owner.pubkey(),
+        &mint,
+        &program_id,
+    );
+    let destination =
+        spl_associated_token_account::get_associated_token_address_with_program_id(&recipient, &mint, &program_id);
+
+    let create_destination = crate::token::create_associated_token_account_idempotent(
+        &owner.pubkey(),
+        &recipient,
+        &mint,
+        token_program,
+    );
+    // spl-token-2022 builds transfer_checked for either token program
+    let transfer = spl_token_2022::instruction::transfer_checked(
+        &program_id,
+        &source,
+        &mint,
+        &destination,
+        &owner.pubkey(),
+        &[],
+        amount,
+        decimals,
+    )
+    .map_err(|e| KeypairError::InvalidInstruction(e.to_string()))?;
+
+    build_transaction(owner, vec![create_destination, transfer], recent_blockhash, options)
+}
+
+// A transaction that only carries a memo
+pub fn build_memo_transaction(
+    signer: &Keypair,
+    memo: &str,
+    recent_blockhash: &str,
+    priority_fee: Option<PriorityFee>,
+) -> Result<Transaction, KeypairError> {
+    let instruction = memo_instruction(memo, &[&signer.pubkey()]);
+    let options = TransactionOptions { priority_fee, memo: None };
+    build_transaction(signer, vec![instruction], recent_blockhash, &options)
+}
+
+// Wire-format bytes of a transaction, encoded for sendTransaction
+pub fn encode_transaction(transaction: &Transaction, encoding: TransactionEncoding) -> Result<String, KeypairError> {
+    let bytes = bincode::serialize(transaction).map_err(|e| KeypairError::InvalidTransaction(e.to_string()))?;
+    Ok(match encoding {
+        TransactionEncoding::Base58 => bs58::encode(bytes).into_string(),
+        TransactionEncoding::Base64 => encode(bytes),
+    })
+}
+
+// Parse a transaction produced by `encode_transaction`
+pub fn decode_transaction(encoded: &str, encoding: TransactionEncoding) -> Result<Transaction, KeypairError> {
+    let bytes = match encoding {
+        TransactionEncoding::Base58 => bs58::decode(encoded.trim()).into_vec()?,
+        TransactionEncoding::Base64 => decode(encoded.trim())?,
+    };
+    bincode::deserialize(&bytes).map_err(|e| KeypairError::InvalidTransaction(e.to_string()))
+}
+
+// Wire-format bytes of a legacy or v0 transaction
+pub fn encode_versioned_transaction(
+    transaction: &VersionedTransaction,
+    encoding: TransactionEncoding,
+) -> Result<String, KeypairError> {
+    let bytes = bincode::serialize(transaction).map_err(|e| KeypairError::InvalidTransaction(e.to_string()))?;
+    Ok(match encoding {
+        TransactionEncoding::Base58 => bs58::encode(bytes).into_string(),
+        TransactionEncoding::Base64 => encode(bytes),
+    })
+}
+
+// Parse either transaction version from its wire-format bytes
+pub fn decode_versioned_transaction(
+    encoded: &str,
+    encoding: TransactionEncoding,
+) -> Result<VersionedTransaction, KeypairError> {
+    let bytes = match encoding {
+        TransactionEncoding::Base58 => bs58::decode(encoded.trim()).into_vec()?,
+        TransactionEncoding::Base64 => decode(encoded.trim())?,
+    };
+    bincode::deserialize(&bytes).map_err(|e| KeypairError::InvalidTransaction(e.to_string()))
+}
+
+// The fee payer's signature, which doubles as the transaction id
+pub fn transaction_signature(transaction: &Transaction) -> Option<String> {
+    transaction.signatures.first().map(|signature| signature.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::keypair_from_seed;
+    use solana_sdk::system_instruction::SystemInstruction;
+    use solana_sdk::system_program;
+    use spl_token_2022::instruction::TokenInstruction;
+
+    const BLOCKHASH: &str = "8ojkxBFz8f1PZYJvrDc5wBR1WBSGXLFUrgnt1cSN1nSW";
+
+    fn keypair(seed: u8) -> Keypair {
+        keypair_from_seed(&[seed; 32]).unwrap()
+    }
+
+    // Program of each instruction, in order
+    fn programs(transaction: &Transaction) -> Vec<Pubkey> {
+        let message = &transaction.message;
+        message
+            .instructions
+            .iter()
+            .map(|instruction| message.account_keys[instruction.program_id_index as usize])
+            .collect()
+    }
+
+    fn options() -> TransactionOptions {
+        TransactionOptions {
+            priority_fee: Some(PriorityFee { compute_unit_limit: Some(300_000), compute_unit_price: Some(10_000) }),
+            memo: Some("invoice 42".to_string()),
+        }
+    }
+
+    #[test]
+    fn priority_fee_sets_limit_before_price() {
+        let fee = PriorityFee { compute_unit_limit: Some(300_000), compute_unit_price: Some(10_000) };
+        assert_eq!(
+            fee.instructions(),
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+                ComputeBudgetInstruction::set_compute_unit_price(10_000),
+            ]
+        );
+        let price_only = PriorityFee { compute_unit_price: Some(1), ..Default::default() };
+        assert_eq!(price_only.instructions(), vec![ComputeBudgetInstruction::set_compute_unit_price(1)]);
+        assert!(PriorityFee::default().instructions().is_empty());
+    }
+
+    #[test]
+    fn options_wrap_instructions_in_order() {
+        let payer = keypair(1).pubkey();
+        let transfer = system_instruction::transfer(&payer, &keypair(2).pubkey(), 1);
+        let instructions = apply_options(&payer, vec![transfer.clone()], &options());
+
+        assert_eq!(instructions.len(), 4);
+        assert_eq!(instructions[0], ComputeBudgetInstruction::set_compute_unit_limit(300_000));
+        assert_eq!(instructions[1], ComputeBudgetInstruction::set_compute_unit_price(10_000));
+        assert_eq!(instructions[2], transfer);
+        assert_eq!(instructions[3], memo_instruction("invoice 42", &[&payer]));
+        assert_eq!(apply_options(&payer, vec![transfer.clone()], &TransactionOptions::default()), vec![transfer]);
+    }
+
+    #[test]
+    fn sol_transfer_is_signed_by_sender() {
+        let from = keypair(1);
+        let recipient = keypair(2).pubkey();
+        let recipient_address = recipient.to_string();
+        let transaction = build_sol_transfer(&from, &recipient_address, 1_500_000_000, BLOCKHASH, &options()).unwrap();
+
+        assert!(transaction.verify().is_ok());
+        assert_eq!(transaction.message.recent_blockhash, parse_blockhash(BLOCKHASH).unwrap());
+        assert_eq!(transaction.message.account_keys[0], from.pubkey());
+        let compute_budget = solana_sdk::compute_budget::id();
+        assert_eq!(programs(&transaction), vec![compute_budget, compute_budget, system_program::id(), spl_memo::id()]);
+
+        let transfer = &transaction.message.instructions[2];
+        assert_eq!(
+            bincode::deserialize::<SystemInstruction>(&transfer.data).unwrap(),
+            SystemInstruction::Transfer { lamports: 1_500_000_000 }
+        );
+        let accounts: Vec<Pubkey> =
+            transfer.accounts.iter().map(|index| transaction.message.account_keys[*index as usize]).collect();
+        assert_eq!(accounts, vec![from.pubkey(), recipient]);
+    }
+
+    #[test]
+    fn spl_transfer_creates_destination_then_transfers_checked() {
+        let owner = keypair(1);
+        let recipient = keypair(2).pubkey();
+        let mint = keypair(3).pubkey();
+
+        for token_program in [TokenProgram::Token, TokenProgram::Token2022] {
+            let transaction = build_spl_transfer(
+                &owner,
+                &recipient.to_string(),
+                &mint.to_string(),
+                1_500_000,
+                6,
+                token_program,
+                BLOCKHASH,
+                &TransactionOptions::default(),
+            )
+            .unwrap();
+            assert!(transaction.verify().is_ok());
+            assert_eq!(programs(&transaction), vec![spl_associated_token_account::id(), token_program.id()]);
+
+            let message = &transaction.message;
+            let keys = |index: usize| -> Vec<Pubkey> {
+                message.instructions[index]
+                    .accounts
+                    .iter()
+                    .map(|account| message.account_keys[*account as usize])
+                    .collect()
+            };
+            let ata = |wallet: &Pubkey| {
+                let program_id = token_program.id();
+                spl_associated_token_account::get_associated_token_address_with_program_id(wallet, &mint, &program_id)
+            };
+
+            // CreateIdempotent: payer, account, wallet, mint, system program, token program
+            assert_eq!(message.instructions[0].data, vec![1]);
+            assert_eq!(keys(0)[..4], [owner.pubkey(), ata(&recipient), recipient, mint]);
+
+            // TransferChecked: source, mint, destination, owner
+            let transfer = TokenInstruction::unpack(&message.instructions[1].data).unwrap();
+            assert_eq!(transfer, TokenInstruction::TransferChecked { amount: 1_500_000, decimals: 6 });
+            assert_eq!(keys(1), vec![ata(&owner.pubkey()), mint, ata(&recipient), owner.pubkey()]);
+        }
+    }
+
+    #[test]
+    fn memo_transaction_signs_memo() {
+        let signer = keypair(1);
+        let fee = PriorityFee { compute_unit_price: Some(1), ..Default::default() };
+        let transaction = build_memo_transaction(&signer, "gm", BLOCKHASH, Some(fee)).unwrap();
+
+        assert!(transaction.verify().is_ok());
+        assert_eq!(programs(&transaction), vec![solana_sdk::compute_budget::id(), spl_memo::id()]);
+        let memo = &transaction.message.instructions[1];
+        assert_eq!(memo.data, b"gm");
+        assert_eq!(transaction.message.account_keys[memo.accounts[0] as usize], signer.pubkey());
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let recipient = keypair(2).pubkey().to_string();
+        let transaction = build_sol_transfer(&keypair(1), &recipient, 1, BLOCKHASH, &options()).unwrap();
+        let bytes = bincode::serialize(&transaction).unwrap();
+
+        for encoding in [TransactionEncoding::Base58, TransactionEncoding::Base64] {
+            let encoded = encode_transaction(&transaction, encoding).unwrap();
+            assert_eq!(decode_transaction(&encoded, encoding).unwrap(), transaction);
+            assert_eq!(decode_transaction(&format!(" {}\n", encoded), encoding).unwrap(), transaction);
+
+            let versioned = decode_versioned_transaction(&encoded, encoding).unwrap();
+            assert_eq!(encode_versioned_transaction(&versioned, encoding).unwrap(), encoded);
+        }
+        let base58 = encode_transaction(&transaction, TransactionEncoding::Base58).unwrap();
+        assert_eq!(base58, bs58::encode(&bytes).into_string());
+        assert_eq!(encode_transaction(&transaction, TransactionEncoding::Base64).unwrap(), encode(&bytes));
+
+        // The fee payer's signature is the transaction id
+        assert_eq!(transaction_signature(&transaction).unwrap(), transaction.signatures[0].to_string());
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(matches!(parse_blockhash("not a hash"), Err(KeypairError::InvalidBlockhash(_))));
+        assert!(build_sol_transfer(&keypair(1), "not a pubkey", 1, BLOCKHASH, &TransactionOptions::default()).is_err());
+        assert!(matches!(
+            decode_transaction(&encode([1, 2, 3]), TransactionEncoding::Base64),
+            Err(KeypairError::InvalidTransaction(_))
+        ));
+    }
+}
@@ -0,0 +1,478 @@
+// JavaScript bindings so the browser can generate and decode keys locally.
+// Built with `wasm-pack build --target web`; wasm-bindgen emits the .d.ts alongside the module.
+// Check it compiles with `cargo build --lib --target wasm32-unknown-unknown` and run the tests at the
+// bottom with `wasm-pack test --node`; CI does both.
+use crate::account::{self, AccountDecodeOptions};
+use crate::derivation::{self, DerivationScheme};
+use crate::keystore;
+use crate::mnemonic;
+use crate::pda::{self, Seed};
+use crate::token;
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use solana_sdk::signature::{Keypair, Signer};
+use std::fmt::Display;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+export interface KeypairJson {
+  pubkey: string;
+  secretKey: string;
+}
+
+export interface DecodedKeypair {
+  pubkey: string;
+  secretKey: Uint8Array;
+}
+
+export interface Keystore {
+  version: number;
+  pubkey: string;
+  kdf: { name: string; logN: number; r: number; p: number; salt: string };
+  cipher: { name: string; nonce: string };
+  ciphertext: string;
+}
+
+export interface DerivedAddress {
+  account: number;
+  path: string;
+  pubkey: string;
+}
+
+export interface ProgramAddress {
+  address: string;
+  bump: number;
+}
+
+export interface TokenExtension {
+  name: string;
+  fields: [string, string][];
+}
+
+export type DecodedAccount =
+  | {
+      type: "mint";
+      tokenProgram: string;
+      mintAuthority: string | null;
+      supply: string;
+      uiSupply: string;
+      decimals: number;
+      isInitialized: boolean;
+      freezeAuthority: string | null;
+      extensions: TokenExtension[];
+    }
+  | {
+      type: "tokenAccount";
+      tokenProgram: string;
+      mint: string;
+      owner: string;
+      amount: string;
+      uiAmount: string | null;
+      delegate: string | null;
+      delegatedAmount: string;
+      state: string;
+      nativeReserve: string | null;
+      closeAuthority: string | null;
+      extensions: TokenExtension[];
+    }
+  | {
+      type: "marketplace";
+      admin: string;
+      feeBasisPoints: number;
+      feePercent: string;
+      totalVolume: string;
+      totalVolumeSol: string;
+      totalFeesCollected: string;
+      totalFeesCollectedSol: string;
+      isInitialized: boolean;
+    }
+  | {
+      type: "sellOrder";
+      // 1 and 2 are legacy layouts left open by earlier versions of the program
+      version: 1 | 2 | 3;
+      marketplace: string | null;
+      seller: string;
+      tokenMint: string;
+      nonce: string | null;
+      amount: string;
+      uiAmount: string | null;
+      pricePerToken: string;
+      pricePerTokenSol: string;
+      createdAt: number;
+      createdAtTime: string | null;
+      isActive: boolean;
+    }
+  | {
+      type: "sellerState";
+      marketplace: string;
+      seller: string;
+      orderCount: string;
+    };
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "KeypairJson")]
+    pub type JsKeypairJson;
+
+    #[wasm_bindgen(typescript_type = "DecodedKeypair")]
+    pub type JsDecodedKeypair;
+
+    #[wasm_bindgen(typescript_type = "Keystore")]
+    pub type JsKeystore;
+
+    #[wasm_bindgen(typescript_type = "Keystore | string")]
+    pub type JsKeystoreInput;
+
+    #[wasm_bindgen(typescript_type = "DerivedAddress[]")]
+    pub type JsDerivedAddresses;
+
+    #[wasm_bindgen(typescript_type = "ProgramAddress")]
+    pub type JsProgramAddress;
+
+    #[wasm_bindgen(typescript_type = "DecodedAccount")]
+    pub type JsDecodedAccount;
+}
+
+// Every failure surfaces in JS as an `Error` named "KeypairError"
+fn js_error(err: impl Display) -> JsValue {
+    let error = js_sys::Error::new(&err.to_string());
+    error.set_name("KeypairError");
+    error.into()
+}
+
+fn set(object: &Object, key: &str, value: &JsValue) -> Result<(), JsValue> {
+    Reflect::set(object, &JsValue::from_str(key), value)?;
+    Ok(())
+}
+
+fn decoded_keypair(keypair: &Keypair) -> Result<JsDecodedKeypair, JsValue> {
+    let object = Object::new();
+    set(&object, "pubkey", &keypair.pubkey().to_string().into())?;
+    set(&object, "secretKey", &Uint8Array::from(&keypair.to_bytes()[..]).into())?;
+    Ok(object.unchecked_into())
+}
+
+#[wasm_bindgen(js_name = generateKeypairJson)]
+pub fn generate_keypair_json() -> Result<JsKeypairJson, JsValue> {
+    let json = crate::generate_keypair_json();
+    Ok(js_sys::JSON::parse(&json)?.unchecked_into())
+}
+
+#[wasm_bindgen(js_name = keypairFromBase58)]
+pub fn keypair_from_base58(base58_string: &str) -> Result<JsDecodedKeypair, JsValue> {
+    let keypair = crate::keypair_from_base58(base58_string).map_err(js_error)?;
+    decoded_keypair(&keypair)
+}
+
+#[wasm_bindgen(js_name = keypairFromBase64)]
+pub fn keypair_from_base64(base64_string: &str) -> Result<JsDecodedKeypair, JsValue> {
+    let keypair = crate::keypair_from_base64(base64_string).map_err(js_error)?;
+    decoded_keypair(&keypair)
+}
+
+// 32 bytes are read as a pubkey unless `isSecretSeed` is true
+#[wasm_bindgen(js_name = pubkeyFromBytes)]
+pub fn pubkey_from_bytes(bytes: &[u8], is_secret_seed: Option<bool>) -> Result<String, JsValue> {
+    let short_key = if is_secret_seed.unwrap_or(false) {
+        crate::ShortKey::SecretSeed
+    } else {
+        crate::ShortKey::Pubkey
+    };
+    crate::pubkey_from_bytes(bytes, short_key)
+        .map(|pubkey| pubkey.to_string())
+        .map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = keypairFromSecretSeed)]
+pub fn keypair_from_secret_seed(seed: &[u8]) -> Result<JsDecodedKeypair, JsValue> {
+    let keypair = crate::keypair_from_secret_seed(seed).map_err(js_error)?;
+    decoded_keypair(&keypair)
+}
+
+#[wasm_bindgen(js_name = fixtureKeypair)]
+pub fn fixture_keypair(label: &str) -> Result<JsDecodedKeypair, JsValue> {
+    decoded_keypair(&crate::fixture_keypair(label))
+}
+
+// Seal a 64-byte secret key under a password; `JSON.stringify` the result to save it
+#[wasm_bindgen(js_name = encryptKeypair)]
+pub fn encrypt_keypair(secret_key: &[u8], password: &str) -> Result<JsKeystore, JsValue> {
+    let keypair = crate::keypair_from_bytes(secret_key).map_err(js_error)?;
+    let json = keystore::encrypt_keypair_json(&keypair, password).map_err(js_error)?;
+    Ok(js_sys::JSON::parse(&json)?.unchecked_into())
+}
+
+// Accepts the keystore object or the text of a saved keystore file
+#[wasm_bindgen(js_name = decryptKeypair)]
+pub fn decrypt_keypair(keystore: JsKeystoreInput, password: &str) -> Result<JsDecodedKeypair, JsValue> {
+    let json = match keystore.as_string() {
+        Some(json) => json,
+        None => String::from(js_sys::JSON::stringify(&keystore)?),
+    };
+    let keypair = keystore::decrypt_keypair_json(&json, password).map_err(js_error)?;
+    decoded_keypair(&keypair)
+}
+
+#[wasm_bindgen(js_name = getAssociatedTokenAddress)]
+pub fn get_associated_token_address(wallet_address: &str, token_mint: &str) -> Result<String, JsValue> {
+    crate::get_associated_token_address(wallet_address, token_mint).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = getAssociatedTokenAddressWithProgramId)]
+pub fn get_associated_token_address_with_program_id(
+    wallet_address: &str,
+    token_mint: &str,
+    token_program_id: &str,
+) -> Result<String, JsValue> {
+    token::get_associated_token_address_with_program_id(wallet_address, token_mint, token_program_id)
+        .map_err(js_error)
+}
+
+// `mint_owner` is the owner field of the mint account, which selects Token or Token-2022
+#[wasm_bindgen(js_name = getAssociatedTokenAddressForMintOwner)]
+pub fn get_associated_token_address_for_mint_owner(
+    wallet_address: &str,
+    token_mint: &str,
+    mint_owner: &str,
+) -> Result<String, JsValue> {
+    token::get_associated_token_address_for_mint_owner(wallet_address, token_mint, mint_owner).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = generateMnemonic)]
+pub fn generate_mnemonic(word_count: usize) -> Result<String, JsValue> {
+    mnemonic::generate_mnemonic(word_count).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = validateMnemonic)]
+pub fn validate_mnemonic(phrase: &str) -> Result<(), JsValue> {
+    mnemonic::validate_mnemonic(phrase).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = keypairFromMnemonic)]
+pub fn keypair_from_mnemonic(phrase: &str, passphrase: Option<String>) -> Result<JsDecodedKeypair, JsValue> {
+    let keypair = mnemonic::keypair_from_mnemonic(phrase, passphrase.as_deref().unwrap_or_default())
+        .map_err(js_error)?;
+    decoded_keypair(&keypair)
+}
+
+#[wasm_bindgen(js_name = keypairFromMnemonicAndPath)]
+pub fn keypair_from_mnemonic_and_path(
+    phrase: &str,
+    passphrase: Option<String>,
+    path: &str,
+) -> Result<JsDecodedKeypair, JsValue> {
+    let keypair =
+        derivation::keypair_from_mnemonic_and_path(phrase, passphrase.as_deref().unwrap_or_default(), path)
+            .map_err(js_error)?;
+    decoded_keypair(&keypair)
+}
+
+// `scheme` is one of "bip44Change", "bip44", "bip44Root" or "legacySollet"
+#[wasm_bindgen(js_name = deriveAddresses)]
+pub fn derive_addresses(
+    phrase: &str,
+    passphrase: Option<String>,
+    scheme: &str,
+    count: u32,
+) -> Result<JsDerivedAddresses, JsValue> {
+    let scheme = match scheme {
+        "bip44Change" => DerivationScheme::Bip44Change,
+        "bip44" => DerivationScheme::Bip44,
+        "bip44Root" => DerivationScheme::Bip44Root,
+        "legacySollet" => DerivationScheme::LegacySollet,
+        other => return Err(js_error(format!("Unknown derivation scheme '{}'", other))),
+    };
+
+    let addresses = derivation::derive_addresses(phrase, passphrase.as_deref().unwrap_or_default(), scheme, count)
+        .map_err(js_error)?;
+
+    let array = Array::new();
+    for address in addresses {
+        let object = Object::new();
+        set(&object, "account", &address.account.into())?;
+        set(&object, "path", &address.path.into())?;
+        set(&object, "pubkey", &address.pubkey.to_string().into())?;
+        array.push(&object);
+    }
+    Ok(array.unchecked_into())
+}
+
+// Decode getAccountInfo data (base64) for a token mint or account, or a marketplace account.
+// `decimals` formats token account and sell order amounts.
+#[wasm_bindgen(js_name = decodeAccount)]
+pub fn decode_account(
+    data_base64: &str,
+    owner: &str,
+    marketplace_program_id: Option<String>,
+    decimals: Option<u8>,
+) -> Result<JsDecodedAccount, JsValue> {
+    let options = AccountDecodeOptions {
+        marketplace_program_id: marketplace_program_id
+            .as_deref()
+            .map(crate::parse_pubkey)
+            .transpose()
+            .map_err(js_error)?,
+        decimals,
+    };
+    let decoded = account::decode_account(data_base64, owner, &options).map_err(js_error)?;
+    let json = serde_json::to_string(&decoded).map_err(js_error)?;
+    Ok(js_sys::JSON::parse(&json)?.unchecked_into())
+}
+
+// Seeds are written as `type:value`: "string:order", "pubkey:<base58>", "u64:7", "u8:1" or "hex:<bytes>"
+#[wasm_bindgen(js_name = findProgramAddress)]
+pub fn find_program_address(seeds: Vec<String>, program_id: &str) -> Result<JsProgramAddress, JsValue> {
+    let seeds = seeds
+        .iter()
+        .map(|seed| seed.parse::<Seed>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(js_error)?;
+    let program_id = crate::parse_pubkey(program_id).map_err(js_error)?;
+    let (address, bump) = pda::find_program_address(&seeds, &program_id).map_err(js_error)?;
+
+    let object = Object::new();
+    set(&object, "address", &address.to_string().into())?;
+    set(&object, "bump", &bump.into())?;
+    Ok(object.unchecked_into())
+}
+
+// The marketplace seller state that holds a seller's order counter
+#[wasm_bindgen(js_name = findSellerStateAddress)]
+pub fn find_seller_state_address(program_id: &str, marketplace: &str, seller: &str) -> Result<String, JsValue> {
+    let parse = |address: &str| crate::parse_pubkey(address).map_err(js_error);
+    let (address, _) = pda::find_seller_state_address(&parse(program_id)?, &parse(marketplace)?, &parse(seller)?);
+    Ok(address.to_string())
+}
+
+// A seller's sell orders for a mint, nonces 0 up to the `orderCount` read from their seller state
+#[wasm_bindgen(js_name = findSellOrderAddresses)]
+pub fn find_sell_order_addresses(
+    program_id: &str,
+    marketplace: &str,
+    seller: &str,
+    mint: &str,
+    order_count: u64,
+) -> Result<Vec<String>, JsValue> {
+    let parse = |address: &str| crate::parse_pubkey(address).map_err(js_error);
+    Ok(pda::find_sell_order_addresses(
+        &parse(program_id)?,
+        &parse(marketplace)?,
+        &parse(seller)?,
+        &parse(mint)?,
+        order_count,
+    )
+    .iter()
+    .map(|address| address.to_string())
+    .collect())
+}
+
+#[wasm_bindgen(js_name = isOnCurve)]
+pub fn is_on_curve(address: &str) -> Result<bool, JsValue> {
+    pda::is_on_curve(address).map_err(js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const SEED_1_PUBKEY: &str = "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9";
+    const SEED_1_SECRET: &str =
+        "2AXDGYSE4f2sz7tvMMzyHvUfcoJmxudvdhBcmiUSo6iuCXagjUCKEQF21awZnUGxmwD4m9vGXuC3qieHXJQHAcT";
+    const MINT: &str = "CktRuQ2mttgRGkXJtyksdKHjUdc2C4TgDzyB98oEzy8";
+
+    fn get(object: &impl AsRef<JsValue>, key: &str) -> JsValue {
+        Reflect::get(object.as_ref(), &JsValue::from_str(key)).unwrap()
+    }
+
+    fn get_string(object: &impl AsRef<JsValue>, key: &str) -> String {
+        get(object, key).as_string().unwrap()
+    }
+
+    // The message of the JS error a binding failed with, checking it is a KeypairError
+    fn error_message<T>(result: Result<T, JsValue>) -> String {
+        let Err(err) = result else { panic!("expected the binding to fail") };
+        let error: js_sys::Error = err.dyn_into().expect("bindings fail with a JS Error");
+        assert_eq!(String::from(error.name()), "KeypairError");
+        error.message().into()
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_keypairs_into_js_objects() {
+        let decoded = keypair_from_base58(SEED_1_SECRET).unwrap();
+        assert_eq!(get_string(&decoded, "pubkey"), SEED_1_PUBKEY);
+        let secret_key: Uint8Array = get(&decoded, "secretKey").dyn_into().unwrap();
+        assert_eq!(secret_key.to_vec(), crate::keypair_from_base58(SEED_1_SECRET).unwrap().to_bytes());
+
+        let fixture = fixture_keypair("alice").unwrap();
+        assert_eq!(get_string(&fixture, "pubkey"), "AyiEneuYD41RV3h1kyjfyzT416yd1GXWvvk1zGzEH4jn");
+        assert_eq!(get_string(&keypair_from_secret_seed(&[1; 32]).unwrap(), "pubkey"), SEED_1_PUBKEY);
+
+        let json = generate_keypair_json().unwrap();
+        assert!(crate::parse_pubkey(&get_string(&json, "pubkey")).is_ok());
+        assert!(crate::keypair_from_base64(&get_string(&json, "secretKey")).is_ok());
+    }
+
+    #[wasm_bindgen_test]
+    fn reads_32_bytes_as_a_pubkey_unless_told_otherwise() {
+        // MINT is the pubkey [3; 32]
+        assert_eq!(pubkey_from_bytes(&[3; 32], None).unwrap(), MINT);
+        assert_eq!(pubkey_from_bytes(&[3; 32], Some(false)).unwrap(), MINT);
+        assert_eq!(pubkey_from_bytes(&[1; 32], Some(true)).unwrap(), SEED_1_PUBKEY);
+    }
+
+    #[wasm_bindgen_test]
+    fn fails_with_a_keypair_error() {
+        let message = error_message(keypair_from_base58("0OIl"));
+        assert_eq!(message, "Invalid base58 character '0' at position 0");
+        let message = error_message(pubkey_from_bytes(&[1; 48], None));
+        assert_eq!(message, "Invalid key length 48: expected 32 or 64 bytes");
+        let message = error_message(derive_addresses("", None, "bip99", 1));
+        assert_eq!(message, "Unknown derivation scheme 'bip99'");
+        let message = error_message(find_program_address(vec!["u32:1".to_string()], SEED_1_PUBKEY));
+        assert!(message.starts_with("Invalid seed: 'u32:1'"), "{}", message);
+    }
+
+    #[wasm_bindgen_test]
+    fn round_trips_a_keystore_as_an_object_or_text() {
+        let secret_key = crate::keypair_from_base58(SEED_1_SECRET).unwrap().to_bytes();
+        let keystore = encrypt_keypair(&secret_key, "hunter2").unwrap();
+        assert_eq!(get_string(&keystore, "pubkey"), SEED_1_PUBKEY);
+
+        let text = js_sys::JSON::stringify(&keystore).unwrap();
+        for input in [JsValue::from(keystore), text.into()] {
+            let decoded = decrypt_keypair(input.clone().unchecked_into(), "hunter2").unwrap();
+            assert_eq!(get_string(&decoded, "pubkey"), SEED_1_PUBKEY);
+            let message = error_message(decrypt_keypair(input.unchecked_into(), "hunter3"));
+            assert_eq!(message, "Wrong password or corrupted keystore");
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn derives_addresses() {
+        let program = "GgBaCs3NCBuZN12kCJgAW63ydqohFkHEdfdEXBPzLHq";
+        let seeds = vec![
+            "string:order".to_string(),
+            format!("pubkey:{}", MINT),
+            "u64:7".to_string(),
+            "u8:1".to_string(),
+            "hex:deadbeef".to_string(),
+        ];
+        let address = find_program_address(seeds, program).unwrap();
+        assert_eq!(get_string(&address, "address"), "6a5Yfp1MvXbBRV46nhd214UuppdPvjGcmz76gZBWab4R");
+        assert_eq!(get(&address, "bump").as_f64(), Some(254.0));
+        assert!(!is_on_curve(&get_string(&address, "address")).unwrap());
+        assert!(is_on_curve(SEED_1_PUBKEY).unwrap());
+
+        let token_2022 = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+        assert_eq!(
+            get_associated_token_address_for_mint_owner(SEED_1_PUBKEY, MINT, token_2022).unwrap(),
+            "9zSrL2GkG2c44qLqdb11v2zuUxkBXLuwt6N2gQYKJu1e"
+        );
+        assert_eq!(
+            get_associated_token_address(SEED_1_PUBKEY, MINT).unwrap(),
+            "Dy41HfQhwNsnQRhKkxL6fGnmAKmQdhAM1h6KStEeWXys"
+        );
+    }
+}